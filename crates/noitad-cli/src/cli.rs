@@ -1,4 +1,6 @@
-use clap::{Parser, Subcommand};
+use std::path::PathBuf;
+
+use clap::{ArgGroup, Parser, Subcommand};
 
 #[derive(Debug, Clone, Parser)]
 #[command(
@@ -26,6 +28,22 @@ pub enum Command {
         #[arg()]
         profile: String,
     },
+    /// Rename an existing mod profile
+    #[command(alias = "mv")]
+    Rename {
+        #[arg()]
+        profile: String,
+        #[arg()]
+        new_name: String,
+    },
+    /// Duplicate an existing mod profile under a new name
+    #[command(alias = "cp")]
+    Copy {
+        #[arg()]
+        profile: String,
+        #[arg()]
+        new_name: String,
+    },
     /// List all existing mod profiles
     #[command(alias = "ls")]
     List,
@@ -34,12 +52,200 @@ pub enum Command {
     Switch {
         #[arg()]
         profile: String,
+        /// If Noita is running, wait for it to exit and switch afterwards
+        #[arg(short, long)]
+        wait: bool,
+    },
+    /// Go back to the game's state from before the last profile switch
+    #[command()]
+    UndoSwitch {
+        /// If Noita is running, wait for it to exit and undo afterwards
+        #[arg(short, long)]
+        wait: bool,
     },
     /// Edit an existing mod profile
     #[command()]
     Edit {
         #[arg(short, long)]
         profile: Option<String>,
+        /// If Noita is running, wait for it to exit before applying the changes
+        #[arg(short, long)]
+        wait: bool,
+    },
+    /// Change the load order of mods in a profile, interactively if no mod is given
+    #[command(group(ArgGroup::new("position").args(["before", "after"]).requires("mod_")))]
+    Reorder {
+        #[arg(short, long)]
+        profile: Option<String>,
+        /// Mod to move, by name or workshop id
+        #[arg(value_name = "MOD", requires = "position")]
+        mod_: Option<String>,
+        /// Place the mod right before this one
+        #[arg(long, value_name = "MOD")]
+        before: Option<String>,
+        /// Place the mod right after this one
+        #[arg(long, value_name = "MOD")]
+        after: Option<String>,
+    },
+    /// Roll the game's mod_config.xml back to one of its backups
+    #[command()]
+    RestoreModConfig {
+        /// List the available backups instead
+        #[arg(short, long, conflicts_with = "backup")]
+        list: bool,
+        /// Name of the backup to restore, picked interactively if not given
+        #[arg()]
+        backup: Option<String>,
+    },
+    /// Show how two mod profiles differ
+    #[command()]
+    Diff {
+        #[arg()]
+        a: String,
+        #[arg()]
+        b: String,
+        /// Print the differences as JSON
+        #[arg(long)]
+        json: bool,
+    },
+    /// Show whether the game's mod list still matches the active profile
+    #[command()]
+    Status,
+    /// Summarize the errors and warnings in the game's log by the mods they came from
+    #[command()]
+    Errors {
+        /// Profile whose mods the log is matched against, the active one if not given
+        #[arg(short, long)]
+        profile: Option<String>,
+        /// Also show the entries that couldn't be tied to a mod
+        #[arg(short, long)]
+        all: bool,
+        /// Print the summary as JSON
+        #[arg(long)]
+        json: bool,
+    },
+    /// Scan the scripts of mods for calls that can harm your computer
+    #[command()]
+    Audit {
+        /// Only scan the mods in this profile, all installed mods are scanned if not given
+        #[arg()]
+        profile: Option<String>,
+        /// Also list the mods nothing was found in
+        #[arg(short, long)]
+        all: bool,
+        /// Print the report as JSON
+        #[arg(long)]
+        json: bool,
+    },
+    /// Remove entries of mods that aren't installed anymore from the active profile and the game's mod list
+    #[command()]
+    Prune {
+        /// Prune every profile instead of just the active one
+        #[arg(short, long)]
+        all_profiles: bool,
+        /// Only list the entries that would be removed
+        #[arg(short = 'n', long)]
+        dry_run: bool,
+    },
+    /// Show which mods of a profile changed since it was saved, like after a workshop update
+    #[command()]
+    Changed {
+        /// The active profile if not given
+        #[arg()]
+        profile: Option<String>,
+        /// Take the mods as they are now as the new baseline
+        #[arg(short, long)]
+        accept: bool,
+    },
+    /// Install a local mod from a .zip, .tar or .tar.gz archive
+    #[command()]
+    Install {
+        #[arg()]
+        archive: PathBuf,
+        /// Replace the mod if it's already installed
+        #[arg(short, long)]
+        force: bool,
+        /// Also enable the mod in the active profile
+        #[arg(short, long)]
+        enable: bool,
+    },
+    /// Create a new local mod and add it to the active profile
+    #[command()]
+    NewMod {
+        /// Folder name of the mod
+        #[arg()]
+        name: String,
+        /// Name shown in the game, the folder name if not given
+        #[arg(short, long)]
+        title: Option<String>,
+        #[arg(short, long, default_value = "")]
+        description: String,
+        /// Also create a settings.lua with an example setting
+        #[arg(short, long)]
+        settings: bool,
+    },
+    /// Move a local mod into the trash and remove it from every profile
+    #[command()]
+    Uninstall {
+        /// Folder name of the mod under the game's mods directory
+        #[arg(value_name = "MOD")]
+        mod_: String,
+    },
+    /// Restore or delete uninstalled mods
+    #[command(arg_required_else_help = true)]
+    Trash {
+        #[command(subcommand)]
+        command: TrashCommand,
+    },
+    /// Link a mod you're developing into the game's mods directory, lists the linked mods if no path is given
+    #[command()]
+    Link {
+        /// Directory of the mod, with its mod.xml
+        #[arg()]
+        path: Option<PathBuf>,
+        /// Copy the mod over instead of symlinking it, link it again to pick up changes
+        #[arg(short, long, requires = "path")]
+        copy: bool,
+    },
+    /// Remove a linked mod from the game's mods directory, its source is left as is
+    #[command()]
+    Unlink {
+        #[arg(value_name = "MOD")]
+        mod_: String,
+    },
+    /// Have a profile use a local copy of a workshop mod, so that workshop updates don't reach it
+    #[command()]
+    Pin {
+        #[arg()]
+        workshop_id: usize,
+        /// The active profile if not given
+        #[arg(short, long)]
+        profile: Option<String>,
+    },
+    /// Have a profile go back to using the workshop version of a pinned mod
+    #[command()]
+    Unpin {
+        /// Name or workshop id of the pinned mod
+        #[arg(value_name = "MOD")]
+        mod_: String,
+        /// The active profile if not given
+        #[arg(short, long)]
+        profile: Option<String>,
+    },
+    /// Keep the active profile in sync with changes the game makes to its mod list
+    #[command()]
+    Watch,
+    /// Find the mods that break the game by trying out smaller and smaller sets of them
+    #[command(arg_required_else_help = true)]
+    Bisect {
+        #[command(subcommand)]
+        command: BisectCommand,
+    },
+    /// Back up and restore the game's save
+    #[command(arg_required_else_help = true)]
+    Save {
+        #[command(subcommand)]
+        command: SaveCommand,
     },
     #[command(arg_required_else_help = true)]
     Config {
@@ -57,3 +263,84 @@ pub enum ConfigCommand {
     #[command()]
     NoitaPath,
 }
+
+#[derive(Debug, Clone, Subcommand)]
+pub enum SaveCommand {
+    /// Snapshot the current save
+    #[command()]
+    Backup {
+        /// Label to remember the snapshot by
+        #[arg(short, long)]
+        label: Option<String>,
+    },
+    /// List all save snapshots
+    #[command(alias = "ls")]
+    List,
+    /// Replace the current save with a snapshot, a snapshot of the current save is kept
+    #[command()]
+    Restore {
+        /// Name of the snapshot to restore, picked interactively if not given
+        #[arg()]
+        snapshot: Option<String>,
+    },
+    /// Give a profile a save of its own, separate from the one shared by other profiles
+    #[command()]
+    Isolate {
+        #[arg()]
+        profile: String,
+    },
+    /// Have a profile go back to using the shared save, its own save is kept around
+    #[command()]
+    Share {
+        #[arg()]
+        profile: String,
+    },
+    /// Delete a save snapshot
+    #[command(alias = "rm")]
+    Delete {
+        #[arg()]
+        snapshot: String,
+    },
+    /// Free up the space used by files that no snapshot needs anymore
+    #[command()]
+    Gc,
+}
+
+#[derive(Debug, Clone, Subcommand)]
+pub enum TrashCommand {
+    /// List the uninstalled mods
+    #[command(alias = "ls")]
+    List,
+    /// Reinstall a mod and put it back into the profiles it was removed from
+    #[command()]
+    Restore {
+        /// Mod name or trash id, picked interactively if not given
+        #[arg(value_name = "MOD")]
+        mod_: Option<String>,
+    },
+    /// Delete all uninstalled mods for good
+    #[command()]
+    Empty,
+}
+
+#[derive(Debug, Clone, Subcommand)]
+pub enum BisectCommand {
+    /// Start bisecting the enabled mods of a profile that breaks the game
+    #[command()]
+    Start {
+        #[arg()]
+        profile: String,
+    },
+    /// The game worked with the current set of mods
+    #[command()]
+    Good,
+    /// The game broke with the current set of mods
+    #[command()]
+    Bad,
+    /// Show the mods enabled for the current step
+    #[command()]
+    Status,
+    /// Stop bisecting and restore the profile's mod list
+    #[command()]
+    Reset,
+}
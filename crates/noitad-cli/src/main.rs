@@ -4,23 +4,38 @@ mod utils;
 use std::{fmt, path::PathBuf, str::FromStr};
 
 use clap::Parser;
-use cli::{ConfigCommand, NoitdCli};
+use cli::{BisectCommand, ConfigCommand, NoitdCli, SaveCommand, TrashCommand};
 use color_eyre::{
     eyre::{bail, ContextCompat, Result},
     owo_colors::OwoColorize,
 };
-use inquire::MultiSelect;
+use inquire::{InquireError, MultiSelect};
 use itertools::Itertools;
 use noitad_lib::{
     config::Config,
     defines::{APP_CONFIG_DIR, APP_CONFIG_PATH},
     ext::PathExt,
     log::RotatingWriter,
-    noita::{mod_config::Mods, GamePath, NoitaPath},
+    noita::{
+        bisect::{Bisect, Verdict},
+        dev_links::LinkedMods,
+        game_log::{GameLog, LogEntry, Severity},
+        lua_audit::{audit_catalog, audit_mods, Risk},
+        mod_catalog::{is_mod_dir_name, ModCatalog, ModInfo},
+        mod_config::{backups::ModConfigBackups, diff::ModsDiff, Mod, ModId, Mods},
+        mod_install::install_archive,
+        mod_scaffold::ModTemplate,
+        mod_trash::{ModTrash, TrashRecord},
+        orphans::InstalledMods,
+        process::NoitaProcess,
+        save_snapshots::{format_size, SaveSnapshots},
+        watcher::ModConfigWatcher,
+        GamePath, NoitaPath, Profile,
+    },
 };
 use tracing::debug;
 use tracing_subscriber::{prelude::*, EnvFilter};
-use utils::group_equal_by_key;
+use utils::{find_mod, group_equal_by_key};
 
 fn get_save_dir(cfg: &Config) -> Result<PathBuf> {
     cfg.noita_path
@@ -28,6 +43,24 @@ fn get_save_dir(cfg: &Config) -> Result<PathBuf> {
         .context("Couldn't find Noita's save directory.")
 }
 
+/// Noita rewrites its mod_config.xml on exit, so there's no point in writing to it while the game is running.
+fn ensure_noita_closed(wait: bool) -> Result<()> {
+    let Some(process) = NoitaProcess::find() else {
+        return Ok(());
+    };
+    if !wait {
+        bail!(
+            "Noita is running (pid {}), close the game first",
+            process.pid
+        )
+    }
+
+    eprintln!("Noita is running, waiting for it to exit...");
+    process.wait();
+
+    Ok(())
+}
+
 macro_rules! exit_on_err {
     ($res:expr) => {{
         match $res {
@@ -55,7 +88,12 @@ fn main() -> Result<()> {
 
     match cli.command {
         cli::Command::Add { profile } => {
-            cfg.profiles.add_profile(&profile, get_save_dir(&cfg)?)?;
+            let mod_list = cfg.profiles.add_profile(&profile, get_save_dir(&cfg)?)?;
+            cfg.profiles.record_mod_hashes(
+                &profile,
+                &mod_list,
+                &InstalledMods::new(&cfg.noita_path),
+            )?;
             if cfg.active_profile.is_none() {
                 cfg.active_profile = Some(profile.to_owned());
             }
@@ -63,49 +101,102 @@ fn main() -> Result<()> {
             eprintln!("Added profile '{}'", profile);
         }
         cli::Command::Remove { profile } => {
-            if cfg.active_profile.as_ref() == Some(&profile) {
-                bail!("Cannot remove an active profile")
-            }
-            cfg.profiles.remove_profile(&profile)?;
+            let snapshot = cfg.remove_profile(&profile)?;
             cfg.store()?;
             eprintln!("Removed profile '{}'", profile);
+            if let Some(snapshot) = snapshot {
+                eprintln!("Its save was kept as snapshot '{}'", snapshot.id);
+            }
+        }
+        cli::Command::Rename { profile, new_name } => {
+            cfg.rename_profile(&profile, &new_name)?;
+            cfg.store()?;
+            eprintln!("Renamed profile '{}' to '{}'", profile, new_name);
+        }
+        cli::Command::Copy { profile, new_name } => {
+            cfg.profiles.duplicate_profile(&profile, &new_name)?;
+            cfg.store()?;
+            eprintln!("Copied profile '{}' to '{}'", profile, new_name);
         }
         cli::Command::List => {
-            if cfg.profiles.keys().len() == 0 {
+            if cfg.profiles.is_empty() {
                 bail!("No profiles available")
             }
+            let catalog = ModCatalog::scan(&cfg.noita_path);
             println!(
                 "{}",
                 cfg.profiles
                     .keys()
-                    .into_iter()
-                    .map(|s| if cfg.active_profile.as_ref() == Some(s) {
-                        format!("* {}", s.green())
-                    } else {
-                        format!("  {}", s)
+                    .map(|s| {
+                        let line = if cfg.active_profile.as_ref() == Some(s) {
+                            format!("* {}", s.green())
+                        } else {
+                            format!("  {}", s)
+                        };
+                        // Flags the profiles with mods that can run arbitrary code
+                        let unrestricted = cfg
+                            .profiles
+                            .get_profile(s)
+                            .map(|mods| {
+                                catalog
+                                    .unrestricted_mods(&mods)
+                                    .iter()
+                                    .filter(|it| it.enabled)
+                                    .count()
+                            })
+                            .unwrap_or_default();
+                        match unrestricted {
+                            0 => line,
+                            n => format!(
+                                "{} {}",
+                                line,
+                                format!("({} mods with unrestricted API access)", n).yellow()
+                            ),
+                        }
                     })
                     .join("\n")
             );
         }
-        cli::Command::Switch { profile } => {
+        cli::Command::Switch { profile, wait } => {
+            Bisect::ensure_not_running()?;
+            ensure_noita_closed(wait)?;
             let noita_save_dir = get_save_dir(&cfg)?;
             let mut mod_list = cfg.profiles.get_profile(&profile)?;
 
             if cfg.active_profile_sync {
                 mod_list.sync_with_noita(&noita_save_dir)?;
             }
-            mod_list.overwrite_noita_mod_list(&noita_save_dir)?;
-            cfg.active_profile = Some(profile.to_owned());
+            cfg.switch_profile(&profile, &mod_list)?;
 
             cfg.store()?;
 
             eprintln!("Switched to profile '{}'", profile);
         }
-        cli::Command::Edit { mut profile } => {
+        cli::Command::UndoSwitch { wait } => {
+            ensure_noita_closed(wait)?;
+            let snapshot = cfg.undo_switch()?;
+            cfg.store()?;
+
+            match &cfg.active_profile {
+                Some(profile) => eprintln!(
+                    "Undid the switch to '{}', back to profile '{}'",
+                    snapshot.record.to, profile
+                ),
+                None => eprintln!("Undid the switch to '{}'", snapshot.record.to),
+            }
+        }
+        cli::Command::Edit { mut profile, wait } => {
             if profile.is_none() {
                 profile = cfg.active_profile.clone();
             }
             let profile = profile.context("No profile is available for edit")?;
+            let is_active = cfg.active_profile.as_ref() == Some(&profile);
+            if is_active || cfg.active_profile_sync {
+                Bisect::ensure_not_running()?;
+            }
+            if is_active && !wait {
+                ensure_noita_closed(false)?;
+            }
 
             let mut mod_list = cfg.profiles.get_profile(&profile)?;
             let noita_save_dir = get_save_dir(&cfg)?;
@@ -113,20 +204,22 @@ fn main() -> Result<()> {
                 mod_list.sync_with_noita(&noita_save_dir)?;
             }
 
-            let (content, enabled) = ModsDisplay::get_vec_from(&mod_list);
+            let catalog = ModCatalog::scan(&cfg.noita_path);
+            let (content, enabled) =
+                ModsDisplay::get_vec_from(&mod_list, &catalog, &cfg.linked_mods);
 
             let selected = exit_on_err!(MultiSelect::new(">", content)
                 .with_default(&enabled)
                 .with_formatter(&|it| format!("{} mods are enabled in total", it.len()))
                 .prompt());
 
-            let dup_modids = group_equal_by_key(&selected, |it| it.1)
-                .values()
-                .filter(|it| it.len() > 1)
-                .map(|it| it[0].1)
+            let dup_modids = group_equal_by_key(&selected, |it| mod_list.mods[it.0].id())
+                .into_iter()
+                .filter(|(_, it)| it.len() > 1)
+                .map(|(id, _)| id)
                 .collect_vec();
 
-            if dup_modids.len() > 0 {
+            if !dup_modids.is_empty() {
                 bail!(
                     "Multiple mods with same id were enabled:\n{}",
                     dup_modids.into_iter().join("\n")
@@ -138,57 +231,842 @@ fn main() -> Result<()> {
                 mod_.enabled = enabled.contains(&i);
             }
 
+            let entry = cfg
+                .profiles
+                .get_mut(&profile)
+                .context("Profile doesn't exist")?;
+            let unapproved = entry.unapproved_mods(&mod_list, &catalog);
+            approve_unrestricted(entry, &mut mod_list, &catalog, unapproved);
+
+            cfg.update_profile(&profile, &mod_list)?;
             cfg.store()?;
 
-            if cfg.active_profile.as_ref() == Some(&profile) {
+            if is_active {
+                ensure_noita_closed(wait)?;
                 mod_list.overwrite_noita_mod_list(&noita_save_dir)?;
             }
         }
-        cli::Command::Config { command, path } => {
-            if path {
-                eprintln!("{}", APP_CONFIG_PATH.to_string_lossy());
+        cli::Command::Reorder {
+            profile,
+            mod_,
+            before,
+            after,
+        } => {
+            let profile = profile
+                .or_else(|| cfg.active_profile.clone())
+                .context("No profile is available for reordering")?;
+
+            let mut mod_list = cfg.profiles.get_profile(&profile)?;
+            let noita_save_dir = get_save_dir(&cfg)?;
+            let is_active = cfg.active_profile.as_ref() == Some(&profile);
+            if is_active {
+                Bisect::ensure_not_running()?;
+                ensure_noita_closed(false)?;
+            }
+            if is_active && cfg.active_profile_sync {
+                mod_list.sync_with_noita(&noita_save_dir)?;
+            }
+
+            match mod_ {
+                Some(query) => {
+                    let id = find_mod(&mod_list, &query)?;
+                    match (before, after) {
+                        (Some(anchor), _) => {
+                            mod_list.move_before(&id, &find_mod(&mod_list, &anchor)?)?
+                        }
+                        (_, Some(anchor)) => {
+                            mod_list.move_after(&id, &find_mod(&mod_list, &anchor)?)?
+                        }
+                        _ => unreachable!("clap requires a position along with the mod"),
+                    }
+                }
+                None => {
+                    let catalog = ModCatalog::scan(&cfg.noita_path);
+                    loop {
+                        let (content, _) =
+                            ModsDisplay::get_vec_from(&mod_list, &catalog, &cfg.linked_mods);
+                        let selected = match inquire::Select::new(
+                            "Mod to move (Esc to finish):",
+                            content.clone(),
+                        )
+                        .raw_prompt()
+                        {
+                            Ok(it) => it,
+                            Err(InquireError::OperationCanceled) => break,
+                            Err(_) => std::process::exit(1),
+                        };
+
+                        let targets = std::iter::once("(Top)".to_owned())
+                            .chain(
+                                content
+                                    .iter()
+                                    .filter(|it| it.0 != selected.index)
+                                    .map(|it| it.to_string()),
+                            )
+                            .collect_vec();
+                        let target = match inquire::Select::new("Place after:", targets)
+                            .with_starting_cursor(selected.index)
+                            .raw_prompt()
+                        {
+                            Ok(it) => it,
+                            Err(InquireError::OperationCanceled) => continue,
+                            Err(_) => std::process::exit(1),
+                        };
+
+                        // Index in the list without the selected mod, offset by the "(Top)" entry
+                        mod_list.move_to(selected.index, target.index)?;
+                    }
+                }
+            }
+
+            cfg.update_profile(&profile, &mod_list)?;
+            cfg.store()?;
+            if is_active {
+                mod_list.overwrite_noita_mod_list(&noita_save_dir)?;
+            }
+
+            eprintln!("Updated load order of profile '{}'", profile);
+        }
+        cli::Command::RestoreModConfig { list, backup } => {
+            let backups = ModConfigBackups::new(get_save_dir(&cfg)?);
+            if !list {
+                Bisect::ensure_not_running()?;
+                ensure_noita_closed(false)?;
+            }
+
+            if list {
+                let backups = backups.list()?;
+                if backups.is_empty() {
+                    bail!("No mod_config backups available")
+                }
+                println!(
+                    "{}",
+                    backups
+                        .iter()
+                        .map(|it| format!("{}  {}", it.name().green(), it))
+                        .join("\n")
+                );
+                return Ok(());
+            }
+
+            let backup = match backup {
+                Some(name) => backups.find(name)?,
+                None => {
+                    let list = backups.list()?;
+                    if list.is_empty() {
+                        bail!("No mod_config backups available")
+                    }
+                    exit_on_err!(inquire::Select::new("Restore backup from:", list).prompt())
+                }
+            };
+            backups.restore(&backup)?;
+
+            eprintln!("Restored mod_config.xml from backup '{}'", backup.name());
+        }
+        cli::Command::Diff { a, b, json } => {
+            let diff = cfg
+                .profiles
+                .get_profile(&a)?
+                .diff(&cfg.profiles.get_profile(&b)?);
+
+            if json {
+                println!("{}", serde_json::to_string_pretty(&diff)?);
+            } else if diff.is_empty() {
+                println!("Profiles '{}' and '{}' have the same mod list", a, b);
+            } else {
+                println!("{} {}\n{} {}", "---".red(), a, "+++".green(), b);
+                let catalog = ModCatalog::scan(&cfg.noita_path);
+                println!("{}", format_mods_diff(&diff, &catalog));
+            }
+        }
+        cli::Command::Status => {
+            let profile = cfg
+                .active_profile
+                .as_ref()
+                .context("No profile is active")?;
+            let diff = cfg.profiles.diff_with_noita(profile, get_save_dir(&cfg)?)?;
+
+            println!("Active profile: {}", profile.green());
+            if diff.is_empty() {
+                println!("The game's mod list matches the profile");
+            } else {
+                println!("The game's mod list was modified outside noitad:");
+                let catalog = ModCatalog::scan(&cfg.noita_path);
+                println!("{}", format_mods_diff(&diff, &catalog));
+            }
+        }
+        cli::Command::Errors { profile, all, json } => {
+            let profile = profile
+                .or(cfg.active_profile.clone())
+                .context("No profile is active, pick one with --profile")?;
+            let game_root = cfg
+                .noita_path
+                .game_root()
+                .context("Couldn't find Noita's game directory.")?;
+            let log = GameLog::from_game_root(&game_root)?;
+            let summary = log.summarize(&cfg.profiles.get_profile(&profile)?);
+
+            if json {
+                println!("{}", serde_json::to_string_pretty(&summary)?);
+                return Ok(());
+            }
+            if summary.mods.is_empty() {
+                println!(
+                    "No errors or warnings from the mods of profile '{}'",
+                    profile
+                );
+            }
+            let catalog = ModCatalog::scan(&cfg.noita_path);
+            for report in summary.mods.iter() {
+                println!(
+                    "{}: {} errors, {} warnings",
+                    mod_title(&catalog, &report.id).bold(),
+                    report.count(Severity::Error),
+                    report.count(Severity::Warning)
+                );
+                println!("{}", format_log_entries(&report.entries));
+            }
+            if !summary.unattributed.is_empty() {
+                match all {
+                    true => {
+                        println!("{}:", "Not tied to a mod".bold());
+                        println!("{}", format_log_entries(&summary.unattributed));
+                    }
+                    false => eprintln!(
+                        "{} more entries couldn't be tied to a mod, show them with --all",
+                        summary.unattributed.len()
+                    ),
+                }
+            }
+        }
+        cli::Command::Audit { profile, all, json } => {
+            let catalog = ModCatalog::scan(&cfg.noita_path);
+            let audits = match &profile {
+                Some(profile) => audit_mods(&cfg.profiles.get_profile(profile)?, &catalog)?,
+                None => audit_catalog(&catalog)?,
+            };
+
+            if json {
+                println!("{}", serde_json::to_string_pretty(&audits)?);
+                return Ok(());
+            }
+            for audit in audits.iter() {
+                let risk = match audit.risk() {
+                    Some(Risk::High) => "high risk".red().to_string(),
+                    Some(Risk::Medium) => "medium risk".yellow().to_string(),
+                    Some(Risk::Low) => "low risk".to_string(),
+                    None if all => "nothing found".green().to_string(),
+                    None => continue,
+                };
+                let unrestricted = match audit.request_no_api_restrictions {
+                    true => ", requests unrestricted API access",
+                    false => "",
+                };
+                println!(
+                    "{} ({}{})",
+                    mod_title(&catalog, &audit.id).bold(),
+                    risk,
+                    unrestricted
+                );
+                for finding in audit.findings.iter() {
+                    println!("  {}", finding);
+                }
+            }
+            let flagged = audits.iter().filter(|it| it.risk().is_some()).count();
+            eprintln!(
+                "Scanned {} mods, found dangerous calls in {}",
+                audits.len(),
+                flagged
+            );
+        }
+        cli::Command::Prune {
+            all_profiles,
+            dry_run,
+        } => {
+            let noita_save_dir = get_save_dir(&cfg)?;
+            let installed = InstalledMods::new(&cfg.noita_path);
+            let profiles = match all_profiles {
+                true => cfg.profiles.keys().sorted().cloned().collect_vec(),
+                false => cfg.active_profile.iter().cloned().collect_vec(),
+            };
+            if !dry_run {
+                Bisect::ensure_not_running()?;
+                ensure_noita_closed(false)?;
+            }
+
+            let print_orphans = |orphans: &[ModId], target: &str| {
+                if orphans.is_empty() {
+                    return;
+                }
+                println!(
+                    "{} {} entries from {}:",
+                    match dry_run {
+                        true => "Would remove",
+                        false => "Removed",
+                    },
+                    orphans.len(),
+                    target
+                );
+                println!(
+                    "{}",
+                    orphans
+                        .iter()
+                        .map(|it| format!("  {} {}", "-".red(), it))
+                        .join("\n")
+                );
+            };
+
+            let mut total = 0;
+            for profile in profiles.iter() {
+                let mut mod_list = cfg.profiles.get_profile(profile)?;
+                let orphans = installed.prune(&mut mod_list);
+                if !dry_run && !orphans.is_empty() {
+                    cfg.profiles.update_profile(profile, &mod_list)?;
+                }
+                print_orphans(&orphans, &format!("profile '{}'", profile));
+                total += orphans.len();
+            }
+
+            let mut mod_list = Mods::from_noita(&noita_save_dir)?;
+            let orphans = installed.prune(&mut mod_list);
+            if !dry_run && !orphans.is_empty() {
+                mod_list.overwrite_noita_mod_list(&noita_save_dir)?;
+            }
+            print_orphans(&orphans, "the game's mod list");
+            total += orphans.len();
+
+            if total == 0 {
+                eprintln!("No entries of missing mods found");
+            }
+        }
+        cli::Command::Changed { profile, accept } => {
+            let profile = profile
+                .or(cfg.active_profile.clone())
+                .context("No profile is active")?;
+            let installed = InstalledMods::new(&cfg.noita_path);
+            let changed = cfg.profiles.changed_mods(&profile, &installed)?;
+
+            if changed.is_empty() {
+                println!(
+                    "No mods of profile '{}' changed since it was saved",
+                    profile
+                );
                 return Ok(());
             }
+            let catalog = ModCatalog::scan(&cfg.noita_path);
+            println!("Mods of profile '{}' changed since it was saved:", profile);
+            println!(
+                "{}",
+                changed
+                    .iter()
+                    .map(|it| format!("  {} {}", "~".yellow(), mod_title(&catalog, it)))
+                    .join("\n")
+            );
+            if accept {
+                let mod_list = cfg.profiles.get_profile(&profile)?;
+                cfg.profiles
+                    .record_mod_hashes(&profile, &mod_list, &installed)?;
+                cfg.store()?;
+                eprintln!("Accepted the changes");
+            }
+        }
+        cli::Command::Install {
+            archive,
+            force,
+            enable,
+        } => {
+            if enable {
+                Bisect::ensure_not_running()?;
+            }
+            let local_mods = cfg
+                .noita_path
+                .local_mods()
+                .context("Couldn't find the game's mods directory")?;
+            let info = install_archive(&archive, &local_mods, force)?;
+            eprintln!("Installed '{}' as '{}'", info.title, info.id.name);
 
+            if enable {
+                let profile = cfg.active_profile.clone().context("No profile is active")?;
+                ensure_noita_closed(false)?;
+                let noita_save_dir = get_save_dir(&cfg)?;
+                let mut mod_list = cfg.profiles.get_profile(&profile)?;
+                match mod_list.position(&info.id) {
+                    Some(i) => mod_list.mods[i].enabled = true,
+                    None => {
+                        let mut mod_ = Mod::new(&info.id.name, 0);
+                        mod_.enabled = true;
+                        mod_list.mods.push(mod_);
+                    }
+                }
+
+                let catalog = ModCatalog::scan(&cfg.noita_path);
+                let entry = cfg
+                    .profiles
+                    .get_mut(&profile)
+                    .context("Profile doesn't exist")?;
+                // Other mods of the profile were already taken care of when they were enabled
+                let unapproved = entry
+                    .unapproved_mods(&mod_list, &catalog)
+                    .into_iter()
+                    .filter(|it| *it == info.id)
+                    .collect_vec();
+                approve_unrestricted(entry, &mut mod_list, &catalog, unapproved);
+
+                cfg.update_profile(&profile, &mod_list)?;
+                cfg.store()?;
+                mod_list.overwrite_noita_mod_list(&noita_save_dir)?;
+                if mod_list
+                    .position(&info.id)
+                    .is_some_and(|i| mod_list.mods[i].enabled)
+                {
+                    eprintln!("Enabled '{}' in profile '{}'", info.title, profile);
+                }
+            }
+        }
+        cli::Command::NewMod {
+            name,
+            title,
+            description,
+            settings,
+        } => {
+            let local_mods = cfg
+                .noita_path
+                .local_mods()
+                .context("Couldn't find the game's mods directory")?;
+            let noita_save_dir = get_save_dir(&cfg)?;
+            Bisect::ensure_not_running()?;
+            ensure_noita_closed(false)?;
+
+            let info = ModTemplate {
+                name,
+                title: title.unwrap_or_default(),
+                description,
+                settings,
+            }
+            .create(&local_mods)?;
+            eprintln!("Created '{}' at {:?}", info.title, info.path);
+
+            let mut new_mod = Mod::new(&info.id.name, 0);
+            new_mod.enabled = true;
+            if let Some(profile) = cfg.active_profile.clone() {
+                let mut mod_list = cfg.profiles.get_profile(&profile)?;
+                mod_list.mods.push(new_mod.clone());
+                cfg.update_profile(&profile, &mod_list)?;
+                cfg.store()?;
+                eprintln!("Enabled it in profile '{}'", profile);
+            }
+            let mut mod_list = Mods::from_noita(&noita_save_dir)?;
+            if mod_list.position(&info.id).is_none() {
+                mod_list.mods.push(new_mod);
+                mod_list.overwrite_noita_mod_list(&noita_save_dir)?;
+            }
+        }
+        cli::Command::Pin {
+            workshop_id,
+            profile,
+        } => {
+            let profile = profile
+                .or(cfg.active_profile.clone())
+                .context("No profile is active")?;
+            let is_active = cfg.active_profile.as_ref() == Some(&profile);
+            if is_active {
+                Bisect::ensure_not_running()?;
+                ensure_noita_closed(false)?;
+            }
+
+            let pinned = cfg.pin_mod(&profile, workshop_id)?;
+            cfg.store()?;
+            if is_active {
+                cfg.profiles
+                    .get_profile(&profile)?
+                    .overwrite_noita_mod_list(get_save_dir(&cfg)?)?;
+            }
+            eprintln!(
+                "Pinned '{}' in profile '{}', it now uses a local copy",
+                pinned.name, profile
+            );
+        }
+        cli::Command::Unpin { mod_, profile } => {
+            let profile = profile
+                .or(cfg.active_profile.clone())
+                .context("No profile is active")?;
+            let is_active = cfg.active_profile.as_ref() == Some(&profile);
+            if is_active {
+                Bisect::ensure_not_running()?;
+                ensure_noita_closed(false)?;
+            }
+
+            let (pinned, trashed) = cfg.unpin_mod(&profile, &mod_)?;
+            cfg.store()?;
+            if is_active {
+                cfg.profiles
+                    .get_profile(&profile)?
+                    .overwrite_noita_mod_list(get_save_dir(&cfg)?)?;
+            }
+            eprintln!(
+                "Unpinned '{}' in profile '{}', it uses the workshop version again",
+                pinned.name, profile
+            );
+            if let Some(trashed) = trashed {
+                eprintln!(
+                    "Moved the unused local copy to the trash as '{}'",
+                    trashed.id
+                );
+            }
+        }
+        cli::Command::Uninstall { mod_ } => {
+            let local_mods = cfg
+                .noita_path
+                .local_mods()
+                .context("Couldn't find the game's mods directory")?;
+            let mod_dir = local_mods.join(&mod_);
+            if !is_mod_dir_name(&mod_) || !mod_dir.is_dir() {
+                bail!("No local mod named '{}' is installed", mod_)
+            }
+            if cfg.linked_mods.contains_key(&mod_) {
+                bail!("'{}' is a linked mod, unlink it instead", mod_)
+            }
+            let noita_save_dir = get_save_dir(&cfg)?;
+            Bisect::ensure_not_running()?;
+            ensure_noita_closed(false)?;
+
+            let id = ModId {
+                name: mod_.clone(),
+                workshop_item_id: 0,
+            };
+            let title = ModInfo::from_dir(&mod_dir, id.clone())
+                .map(|it| it.title)
+                .unwrap_or_else(|_| mod_.clone());
+            let placements = cfg.profiles.remove_mod(&id)?;
+            let record = TrashRecord {
+                name: mod_.clone(),
+                title: title.clone(),
+                profiles: placements.clone(),
+            };
+            if let Err(err) = ModTrash::default().trash(&mod_dir, &local_mods, record) {
+                cfg.profiles.restore_mod(&id, &placements)?;
+                return Err(err);
+            }
+
+            let mut mod_list = Mods::from_noita(&noita_save_dir)?;
+            if let Some(i) = mod_list.position(&id) {
+                mod_list.mods.remove(i);
+                mod_list.overwrite_noita_mod_list(&noita_save_dir)?;
+            }
+
+            eprintln!("Moved '{}' to the trash", title);
+            if !placements.is_empty() {
+                eprintln!(
+                    "Removed it from profiles: {}",
+                    placements.iter().map(|it| &it.profile).join(", ")
+                );
+            }
+        }
+        cli::Command::Trash { command } => {
+            let trash = ModTrash::default();
             match command {
-                Some(ConfigCommand::NoitaPath) => {
-                    let resolver = exit_on_err!(inquire::Select::new(
-                        "Pick one of the path resolver",
-                        vec!["Steam", "Manual"],
-                    )
-                    .prompt());
-
-                    match resolver {
-                        "Steam" => {
-                            cfg.noita_path = NoitaPath::Steam;
+                TrashCommand::List => {
+                    let list = trash.list()?;
+                    if list.is_empty() {
+                        bail!("The trash is empty")
+                    }
+                    println!(
+                        "{}",
+                        list.iter()
+                            .map(|it| format!("{}  {}", it.id.green(), it))
+                            .join("\n")
+                    );
+                }
+                TrashCommand::Restore { mod_ } => {
+                    let local_mods = cfg
+                        .noita_path
+                        .local_mods()
+                        .context("Couldn't find the game's mods directory")?;
+                    let noita_save_dir = get_save_dir(&cfg)?;
+                    Bisect::ensure_not_running()?;
+                    ensure_noita_closed(false)?;
+
+                    let trashed = match mod_ {
+                        Some(query) => trash.find(query)?,
+                        None => {
+                            let list = trash.list()?;
+                            if list.is_empty() {
+                                bail!("The trash is empty")
+                            }
+                            exit_on_err!(inquire::Select::new("Restore mod:", list).prompt())
                         }
-                        "Manual" => {
-                            let game_root = PathBuf::from_str(&exit_on_err!(inquire::Text::new(
-                                "Game's root directory path:"
-                            )
-                            .prompt()))?
-                            .try_is_dir()?;
+                    };
+                    trash.restore(&trashed, &local_mods)?;
 
-                            let mut manual = GamePath {
-                                game_root,
-                                ..Default::default()
-                            };
-
-                            #[cfg(unix)]
-                            {
-                                let wine_prefix = PathBuf::from_str(&exit_on_err!(
-                                    inquire::Text::new("Game's wine prefix path:").prompt()
-                                ))?
-                                .try_is_dir()?;
-                                manual.wine_prefix = Some(wine_prefix);
+                    let id = ModId {
+                        name: trashed.record.name.clone(),
+                        workshop_item_id: 0,
+                    };
+                    let restored = cfg.profiles.restore_mod(&id, &trashed.record.profiles)?;
+                    // The game's mod list follows the active profile
+                    let active = trashed
+                        .record
+                        .profiles
+                        .iter()
+                        .find(|it| Some(&it.profile) == cfg.active_profile.as_ref());
+                    if let Some(placement) = active.filter(|it| restored.contains(&it.profile)) {
+                        let mut mod_list = Mods::from_noita(&noita_save_dir)?;
+                        if mod_list.position(&id).is_none() {
+                            let mut mod_ = Mod::new(&id.name, 0);
+                            mod_.enabled = placement.enabled;
+                            mod_list
+                                .mods
+                                .insert(placement.position.min(mod_list.mods.len()), mod_);
+                            mod_list.overwrite_noita_mod_list(&noita_save_dir)?;
+                        }
+                    }
+
+                    eprintln!("Restored '{}'", trashed.record.title);
+                    if !restored.is_empty() {
+                        eprintln!("Put it back into profiles: {}", restored.join(", "));
+                    }
+                }
+                TrashCommand::Empty => {
+                    let count = trash.empty()?;
+                    eprintln!("Deleted {} mods for good", count);
+                }
+            }
+        }
+        cli::Command::Link { path, copy } => {
+            let local_mods = cfg
+                .noita_path
+                .local_mods()
+                .context("Couldn't find the game's mods directory")?;
+            let Some(path) = path else {
+                if cfg.linked_mods.is_empty() {
+                    bail!("No mods are linked")
+                }
+                println!(
+                    "{}",
+                    cfg.linked_mods
+                        .iter()
+                        .map(|(name, linked)| {
+                            let line = format!(
+                                "{} {} {:?}{}",
+                                name.green(),
+                                "->".dimmed(),
+                                linked.source,
+                                if linked.copied { " (copy)" } else { "" }
+                            );
+                            match cfg.linked_mods.is_intact(name, &local_mods) {
+                                true => line,
+                                false => format!("{} {}", line, "(link is broken)".red()),
                             }
+                        })
+                        .join("\n")
+                );
+                return Ok(());
+            };
+
+            let info = cfg.linked_mods.link(&path, &local_mods, copy)?;
+            cfg.store()?;
+            eprintln!("Linked '{}' as '{}'", info.title, info.id.name);
+        }
+        cli::Command::Unlink { mod_ } => {
+            let local_mods = cfg
+                .noita_path
+                .local_mods()
+                .context("Couldn't find the game's mods directory")?;
+            ensure_noita_closed(false)?;
+            let linked = cfg.linked_mods.unlink(&mod_, &local_mods)?;
+            cfg.store()?;
+            eprintln!("Unlinked '{}', it's still at {:?}", mod_, linked.source);
+        }
+        cli::Command::Watch => {
+            let noita_save_dir = get_save_dir(&cfg)?;
+            let (tx, rx) = std::sync::mpsc::channel();
+            let _watcher = ModConfigWatcher::new(&noita_save_dir, move || _ = tx.send(()))?;
 
-                            cfg.store()?;
+            eprintln!("Watching {:?} for changes...", noita_save_dir);
+            while rx.recv().is_ok() {
+                // The active profile might've been switched by another instance in the meantime
+                let cfg = Config::load()?;
+                let Some(profile) = cfg.active_profile else {
+                    continue;
+                };
+                match cfg
+                    .profiles
+                    .sync_profile_with_noita(&profile, &noita_save_dir)
+                {
+                    Ok(true) => eprintln!("Synced profile '{}' with the game", profile),
+                    Ok(false) => {}
+                    Err(err) => eprintln!("Failed to sync profile '{}': {}", profile, err),
+                }
+            }
+        }
+        cli::Command::Bisect { command } => {
+            let noita_save_dir = get_save_dir(&cfg)?;
+            let catalog = ModCatalog::scan(&cfg.noita_path);
+            match command {
+                BisectCommand::Start { profile } => {
+                    ensure_noita_closed(false)?;
+                    let mod_list = cfg.profiles.get_profile(&profile)?;
+                    if cfg.active_profile.as_ref() != Some(&profile) {
+                        // So that the profile's save is the one being played
+                        cfg.switch_profile(&profile, &mod_list)?;
+                        cfg.store()?;
+                        eprintln!("Switched to profile '{}'", profile);
+                    }
+                    let bisect = Bisect::start(&profile, &mod_list, &noita_save_dir)?;
+                    print_bisect_step(&bisect, &catalog);
+                }
+                BisectCommand::Good | BisectCommand::Bad => {
+                    let mut bisect = Bisect::load()?.context("No bisect is in progress")?;
+                    let verdict = match command {
+                        BisectCommand::Good => Verdict::Good,
+                        _ => Verdict::Bad,
+                    };
+                    let mod_list = cfg.profiles.get_profile(&bisect.profile)?;
+                    match bisect.answer(verdict, &mod_list, &noita_save_dir)? {
+                        Some(culprits) => {
+                            println!("The problem is caused by:");
+                            println!(
+                                "{}",
+                                culprits
+                                    .iter()
+                                    .map(|it| format!("  {}", mod_title(&catalog, it).red()))
+                                    .join("\n")
+                            );
+                            eprintln!("Restored the mod list of profile '{}'", bisect.profile);
                         }
-                        _ => unreachable!(),
+                        None => print_bisect_step(&bisect, &catalog),
                     }
                 }
-                _ => {}
+                BisectCommand::Status => {
+                    let bisect = Bisect::load()?.context("No bisect is in progress")?;
+                    print_bisect_step(&bisect, &catalog);
+                }
+                BisectCommand::Reset => {
+                    let bisect = Bisect::load()?.context("No bisect is in progress")?;
+                    ensure_noita_closed(false)?;
+                    let mod_list = cfg.profiles.get_profile(&bisect.profile)?;
+                    bisect.reset(&mod_list, &noita_save_dir)?;
+                    eprintln!("Restored the mod list of profile '{}'", bisect.profile);
+                }
+            }
+        }
+        cli::Command::Save { command } => {
+            let snapshots = SaveSnapshots::default();
+            match command {
+                SaveCommand::Backup { label } => {
+                    let snapshot = snapshots.create(get_save_dir(&cfg)?, label)?;
+                    eprintln!("Backed up save as '{}'", snapshot.id);
+                }
+                SaveCommand::List => {
+                    let list = snapshots.list()?;
+                    if list.is_empty() {
+                        bail!("No save snapshots available")
+                    }
+                    println!(
+                        "{}",
+                        list.iter()
+                            .map(|it| format!("{}  {}", it.id.green(), it))
+                            .join("\n")
+                    );
+                    let usage = snapshots.usage()?;
+                    eprintln!(
+                        "{} snapshots, {} in total, {} on disk",
+                        list.len(),
+                        format_size(usage.logical),
+                        format_size(usage.stored)
+                    );
+                }
+                SaveCommand::Restore { snapshot } => {
+                    let noita_save_dir = get_save_dir(&cfg)?;
+                    ensure_noita_closed(false)?;
+
+                    let snapshot = match snapshot {
+                        Some(id) => snapshots.find(id)?,
+                        None => {
+                            let list = snapshots.list()?;
+                            if list.is_empty() {
+                                bail!("No save snapshots available")
+                            }
+                            exit_on_err!(inquire::Select::new("Restore save from:", list).prompt())
+                        }
+                    };
+                    let safety = snapshots.restore(&snapshot, &noita_save_dir)?;
+
+                    eprintln!("Restored save from snapshot '{}'", snapshot.id);
+                    if let Some(safety) = safety {
+                        eprintln!("The replaced save was kept as '{}'", safety.id);
+                    }
+                }
+                SaveCommand::Isolate { profile } => {
+                    cfg.set_isolated_save(&profile, true)?;
+                    cfg.store()?;
+                    eprintln!("Profile '{}' now has a save of its own", profile);
+                }
+                SaveCommand::Share { profile } => {
+                    cfg.set_isolated_save(&profile, false)?;
+                    cfg.store()?;
+                    eprintln!("Profile '{}' now uses the shared save", profile);
+                }
+                SaveCommand::Delete { snapshot } => {
+                    let snapshot = snapshots.find(snapshot)?;
+                    snapshots.remove(&snapshot)?;
+                    eprintln!("Deleted save snapshot '{}'", snapshot.id);
+                }
+                SaveCommand::Gc => {
+                    let report = snapshots.gc()?;
+                    let usage = snapshots.usage()?;
+                    eprintln!(
+                        "Freed {} from {} unused blobs, snapshots now take up {} on disk for {} of saves",
+                        format_size(report.freed),
+                        report.removed,
+                        format_size(usage.stored),
+                        format_size(usage.logical)
+                    );
+                }
+            }
+        }
+        cli::Command::Config { command, path } => {
+            if path {
+                eprintln!("{}", APP_CONFIG_PATH.to_string_lossy());
+                return Ok(());
+            }
+
+            if let Some(ConfigCommand::NoitaPath) = command {
+                let resolver = exit_on_err!(inquire::Select::new(
+                    "Pick one of the path resolver",
+                    vec!["Steam", "Manual"],
+                )
+                .prompt());
+
+                match resolver {
+                    "Steam" => {
+                        cfg.noita_path = NoitaPath::Steam;
+                    }
+                    "Manual" => {
+                        let game_root = PathBuf::from_str(&exit_on_err!(inquire::Text::new(
+                            "Game's root directory path:"
+                        )
+                        .prompt()))?
+                        .try_is_dir()?;
+
+                        let mut manual = GamePath {
+                            game_root,
+                            ..Default::default()
+                        };
+
+                        #[cfg(unix)]
+                        {
+                            let wine_prefix = PathBuf::from_str(&exit_on_err!(
+                                inquire::Text::new("Game's wine prefix path:").prompt()
+                            ))?
+                            .try_is_dir()?;
+                            manual.wine_prefix = Some(wine_prefix);
+                        }
+
+                        cfg.store()?;
+                    }
+                    _ => unreachable!(),
+                }
             }
         }
     };
@@ -196,17 +1074,138 @@ fn main() -> Result<()> {
     Ok(())
 }
 
+fn print_bisect_step(bisect: &Bisect, catalog: &ModCatalog) {
+    let mods = bisect.step_mods();
+    println!(
+        "Bisecting profile '{}', enabled {} mods for this step:",
+        bisect.profile.green(),
+        mods.len()
+    );
+    println!(
+        "{}",
+        mods.iter()
+            .map(|it| format!("  {}", mod_title(catalog, it)))
+            .join("\n")
+    );
+    if !bisect.culprits().is_empty() {
+        eprintln!(
+            "Found so far: {}",
+            bisect
+                .culprits()
+                .iter()
+                .map(|it| mod_title(catalog, it))
+                .join(", ")
+        );
+    }
+    eprintln!(
+        "{} mods still suspected, about {} steps left",
+        bisect.suspects(),
+        bisect.steps_left()
+    );
+    eprintln!("Launch the game, then answer with `bisect good` or `bisect bad`");
+}
+
+fn format_log_entries(entries: &[LogEntry]) -> String {
+    entries
+        .iter()
+        .map(|it| {
+            let count = match it.count {
+                1 => String::new(),
+                n => format!(" (x{})", n),
+            };
+            match it.severity {
+                Severity::Error => format!("  {}{}", it.message.red(), count),
+                Severity::Warning => format!("  {}{}", it.message.yellow(), count),
+            }
+        })
+        .join("\n")
+}
+
+/// Asks whether to enable each of the `unapproved` mods, which request unrestricted API access,
+/// the declined ones are disabled again.
+fn approve_unrestricted(
+    entry: &mut Profile,
+    mod_list: &mut Mods,
+    catalog: &ModCatalog,
+    unapproved: Vec<ModId>,
+) {
+    for id in unapproved {
+        let title = mod_title(catalog, &id);
+        let approved = exit_on_err!(inquire::Confirm::new(&format!(
+            "'{}' requests unrestricted API access, which lets it run any code on your computer. Enable it anyway?",
+            title
+        ))
+        .with_default(false)
+        .prompt());
+        match approved {
+            true => _ = entry.approved_unrestricted.insert(id),
+            false => {
+                if let Some(i) = mod_list.position(&id) {
+                    mod_list.mods[i].enabled = false;
+                }
+                eprintln!("Left '{}' disabled", title);
+            }
+        }
+    }
+}
+
+/// Title of an installed mod along with its id, or just its id if it isn't installed.
+fn mod_title(catalog: &ModCatalog, id: &ModId) -> String {
+    match catalog.get(id) {
+        Some(info) if info.title != id.name => format!("{} [{}]", info.title, id),
+        _ => id.to_string(),
+    }
+}
+
+fn format_mods_diff(diff: &ModsDiff, catalog: &ModCatalog) -> String {
+    let title = |id: &ModId| mod_title(catalog, id);
+
+    diff.added
+        .iter()
+        .map(|it| format!("{} {}", "+".green(), title(it)))
+        .chain(
+            diff.removed
+                .iter()
+                .map(|it| format!("{} {}", "-".red(), title(it))),
+        )
+        .chain(
+            diff.enabled
+                .iter()
+                .map(|it| format!("{} {} (enabled)", "~".yellow(), title(it))),
+        )
+        .chain(
+            diff.disabled
+                .iter()
+                .map(|it| format!("{} {} (disabled)", "~".yellow(), title(it))),
+        )
+        .chain(
+            diff.reordered
+                .iter()
+                .map(|it| format!("{} {} (moved)", "↕".blue(), title(it))),
+        )
+        .join("\n")
+}
+
 /// All this because inquire wouldn't let me just let me give it a closure where I can return a string from the vec's items.
 #[derive(Debug, Clone)]
-struct ModsDisplay<'a>(usize, &'a str, bool);
+struct ModsDisplay<'a>(usize, &'a str, &'static str);
 
 impl<'a> ModsDisplay<'a> {
-    fn get_vec_from(value: &'a Mods) -> (Vec<Self>, Vec<usize>) {
+    fn get_vec_from(
+        value: &'a Mods,
+        catalog: &'a ModCatalog,
+        linked: &LinkedMods,
+    ) -> (Vec<Self>, Vec<usize>) {
         let mut content = vec![];
         let mut enabled = vec![];
 
         for (i, mod_) in value.mods.iter().enumerate() {
-            content.push(Self(i, mod_.name.as_str(), mod_.workshop_item_id == 0));
+            let source = match mod_.is_local() {
+                true if linked.contains_key(&mod_.name) => "Dev",
+                true => "Local",
+                false => "Steam",
+            };
+            content.push(Self(i, catalog.title(mod_), source));
             if mod_.enabled {
                 enabled.push(i);
             }
@@ -218,10 +1217,6 @@ impl<'a> ModsDisplay<'a> {
 
 impl fmt::Display for ModsDisplay<'_> {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        f.write_fmt(format_args!(
-            "{} ({})",
-            self.1,
-            if self.2 { "Local" } else { "Steam" }
-        ))
+        f.write_fmt(format_args!("{} ({})", self.1, self.2))
     }
 }
@@ -1,5 +1,9 @@
 use std::collections::HashMap;
 
+use color_eyre::eyre::{bail, Result};
+use itertools::Itertools;
+use noitad_lib::noita::mod_config::{ModId, Mods};
+
 pub fn group_equal_by_key<T, U>(arr: &[T], key: impl Fn(&T) -> U) -> HashMap<U, Vec<&T>>
 where
     U: std::hash::Hash + std::cmp::Eq,
@@ -7,8 +11,32 @@ where
     let mut groups: HashMap<U, Vec<&T>> = HashMap::new();
 
     for item in arr.iter() {
-        groups.entry(key(item)).or_insert_with(Vec::new).push(item);
+        groups.entry(key(item)).or_default().push(item);
     }
 
     groups
 }
+
+/// Finds a mod entry by either its name or workshop id.
+pub fn find_mod(mod_list: &Mods, query: &str) -> Result<ModId> {
+    let workshop_item_id = query.parse::<usize>().ok().filter(|it| *it != 0);
+    let found = mod_list
+        .mods
+        .iter()
+        .filter(|it| it.name == query || Some(it.workshop_item_id) == workshop_item_id)
+        .map(|it| it.id())
+        .collect_vec();
+
+    match found.as_slice() {
+        [] => bail!("No mod named '{}' in the profile", query),
+        [id] => Ok(id.clone()),
+        _ => bail!(
+            "Multiple mods match '{}', use the workshop id instead:\n{}",
+            query,
+            found
+                .iter()
+                .map(|it| format!("{} ({})", it.name, it.workshop_item_id))
+                .join("\n")
+        ),
+    }
+}
@@ -1,10 +1,26 @@
+use std::path::PathBuf;
+
 use better_default::Default;
-use color_eyre::eyre::{self, Result};
+use color_eyre::eyre::{self, bail, ContextCompat, Result, WrapErr};
+use fs_err as fs;
 use serde::{Deserialize, Serialize};
+use tracing::warn;
 
 use crate::{
     defines::APP_CONFIG_PATH,
-    noita::{ModProfiles, NoitaPath},
+    noita::{
+        bisect::Bisect,
+        dev_links::LinkedMods,
+        mod_config::{backups::write_mod_config, Mods},
+        mod_trash::{ModTrash, TrashRecord, TrashedMod},
+        orphans::InstalledMods,
+        pinned_mods::PinnedMod,
+        process::NoitaProcess,
+        profile_saves::{ProfileSaves, SHARED_SLOT},
+        save_snapshots::{SaveSnapshot, SaveSnapshots},
+        switch_snapshots::{SafetySnapshotPolicy, SwitchSnapshot, SwitchSnapshots},
+        ModProfiles, NoitaPath,
+    },
 };
 
 #[derive(Debug, Default, Clone, Serialize, Deserialize)]
@@ -16,14 +32,331 @@ pub struct Config {
     /// Sync it with noita's `mod_config.xml`
     #[default(true)]
     pub active_profile_sync: bool,
+    /// Snapshots taken before switching profiles, for [`Config::undo_switch`]
+    ///
+    /// Other writes to `mod_config.xml` are covered by its backups instead, see [`write_mod_config`]
+    pub safety_snapshots: SafetySnapshotPolicy,
+    /// Mods under development linked into the game's mods directory
+    pub linked_mods: LinkedMods,
 }
 
 impl Config {
     pub fn load() -> Result<Self> {
-        confy::load_path::<Self>(APP_CONFIG_PATH.as_path()).map_err(eyre::Report::msg)
+        let mut cfg =
+            confy::load_path::<Self>(APP_CONFIG_PATH.as_path()).map_err(eyre::Report::msg)?;
+        if cfg.profiles.migrate()? {
+            cfg.store()?;
+        }
+
+        Ok(cfg)
     }
     pub fn store(&self) -> Result<()> {
-        confy::store_path(APP_CONFIG_PATH.as_path(), &self)?;
+        confy::store_path(APP_CONFIG_PATH.as_path(), self)?;
+        Ok(())
+    }
+    /// Removes a profile other than the active one.
+    ///
+    /// A save of its own is kept as a save snapshot, which is returned.
+    pub fn remove_profile(&mut self, profile: impl AsRef<str>) -> Result<Option<SaveSnapshot>> {
+        if self.active_profile.as_deref() == Some(profile.as_ref()) {
+            bail!("Cannot remove an active profile")
+        }
+        let entry = self.profiles.get(profile.as_ref()).with_context(|| {
+            format!(
+                "Profile '{}' does not exist and cannot be removed",
+                profile.as_ref()
+            )
+        })?;
+
+        let snapshot = match entry.isolated_save {
+            true => ProfileSaves::new(self.save_dir()?).remove(
+                &entry.id,
+                format!("Save of removed profile '{}'", profile.as_ref()),
+            )?,
+            false => None,
+        };
+        self.profiles.remove_profile(profile.as_ref())?;
+
+        Ok(snapshot)
+    }
+    /// Renames a profile, keeping [`Config::active_profile`] pointed at it if it was the active one.
+    pub fn rename_profile(
+        &mut self,
+        profile: impl AsRef<str>,
+        new_name: impl AsRef<str>,
+    ) -> Result<()> {
+        self.profiles
+            .rename_profile(profile.as_ref(), new_name.as_ref())?;
+        if self.active_profile.as_deref() == Some(profile.as_ref()) {
+            self.active_profile = Some(new_name.as_ref().to_owned());
+        }
+
         Ok(())
     }
+    /// Writes the profile's mod list, hashing mods new to it to later tell which of them changed.
+    pub fn update_profile(&mut self, profile: impl AsRef<str>, mod_list: &Mods) -> Result<()> {
+        self.profiles.update_profile(profile.as_ref(), mod_list)?;
+        self.profiles.update_mod_hashes(
+            profile.as_ref(),
+            mod_list,
+            &InstalledMods::new(&self.noita_path),
+        )
+    }
+    /// Makes `profile` the active one, writing `mod_list` to the game and swapping in the profile's save.
+    ///
+    /// The game's state is snapshotted first according to [`Config::safety_snapshots`].
+    pub fn switch_profile(&mut self, profile: impl AsRef<str>, mod_list: &Mods) -> Result<()> {
+        let save_dir = self.save_dir()?;
+        if !self.profiles.contains_key(profile.as_ref()) {
+            bail!("Profile '{}' doesn't exist", profile.as_ref())
+        }
+        Bisect::ensure_not_running()?;
+
+        let snapshots = SwitchSnapshots::default();
+        let snapshot = match self.safety_snapshots.enabled {
+            true => Some(snapshots.create(
+                &save_dir,
+                self.active_profile.clone(),
+                profile.as_ref(),
+                self.safety_snapshots.include_save,
+            )?),
+            false => None,
+        };
+
+        let from = self.active_save_slot().to_owned();
+        let to = self.profiles[profile.as_ref()].save_slot().to_owned();
+        // There's no switch to undo when it fails
+        let remove_snapshot = || {
+            if let Some(snapshot) = &snapshot {
+                _ = snapshots.remove(snapshot).inspect_err(|e| warn!(%e));
+            }
+        };
+        if let Err(err) = self.swap_save_to(&to) {
+            remove_snapshot();
+            return Err(err);
+        }
+        if let Err(err) = mod_list.overwrite_noita_mod_list(&save_dir) {
+            // The snapshot is kept to undo with if the previous save can't be put back
+            self.swap_save_back(&to, &from, &err)?;
+            remove_snapshot();
+            return Err(err);
+        }
+        self.active_profile = Some(profile.as_ref().to_owned());
+
+        if snapshot.is_some() {
+            _ = snapshots
+                .prune(&self.safety_snapshots)
+                .inspect_err(|err| warn!(%err, "Couldn't prune switch snapshots"));
+        }
+
+        Ok(())
+    }
+    /// Puts the game back into the state it was in before the last profile switch.
+    ///
+    /// The snapshot is used up by this, so undoing again goes back another switch.
+    pub fn undo_switch(&mut self) -> Result<SwitchSnapshot> {
+        if let Some(process) = NoitaProcess::find() {
+            bail!(
+                "Noita is running (pid {}), close the game before undoing a switch",
+                process.pid
+            )
+        }
+        Bisect::ensure_not_running()?;
+        let save_dir = self.save_dir()?;
+        let snapshots = SwitchSnapshots::default();
+        let snapshot = snapshots
+            .list()?
+            .into_iter()
+            .next()
+            .context("There's no profile switch to undo")?;
+
+        // The profile might've been removed since, in which case its save is gone as well
+        let from = snapshot
+            .record
+            .from
+            .clone()
+            .filter(|it| self.profiles.contains_key(it));
+        let current = self.active_save_slot().to_owned();
+        let to = from
+            .as_ref()
+            .map_or(SHARED_SLOT, |it| self.profiles[it].save_slot())
+            .to_owned();
+        self.swap_save_to(&to)?;
+        let res = (|| -> Result<()> {
+            if let Some(id) = &snapshot.record.save_snapshot {
+                let save_snapshots = SaveSnapshots::default();
+                save_snapshots.restore(&save_snapshots.find(id)?, &save_dir)?;
+            }
+            if let Some(mod_config) = snapshot.mod_config() {
+                write_mod_config(&save_dir, fs::read(mod_config)?)?;
+            }
+            Ok(())
+        })();
+        if let Err(err) = res {
+            self.swap_save_back(&to, &current, &err)?;
+            return Err(err);
+        }
+        self.active_profile = from;
+        snapshots.remove(&snapshot)?;
+
+        Ok(snapshot)
+    }
+    /// Swaps the game's save over to the one used by `profile`, for when switching to it.
+    pub fn swap_save_for(&self, profile: impl AsRef<str>) -> Result<()> {
+        let to = self
+            .profiles
+            .get(profile.as_ref())
+            .with_context(|| format!("Profile '{}' doesn't exist", profile.as_ref()))?
+            .save_slot();
+        self.swap_save_to(to)
+    }
+    fn swap_save_to(&self, to: &str) -> Result<()> {
+        let from = self.active_save_slot();
+        if from == to {
+            return Ok(());
+        }
+
+        ProfileSaves::new(self.save_dir()?).swap(from, to)
+    }
+    /// Puts the save used before [`Config::swap_save_to`] back after a later step failed with `err`.
+    fn swap_save_back(&self, to: &str, from: &str, err: &eyre::Report) -> Result<()> {
+        if from == to {
+            return Ok(());
+        }
+
+        ProfileSaves::new(self.save_dir()?)
+            .swap(to, from)
+            .with_context(|| format!("Couldn't swap the previous save back after: {err}"))
+    }
+    /// Slot of the save that's currently in the game's save directory.
+    fn active_save_slot(&self) -> &str {
+        self.active_profile
+            .as_ref()
+            .and_then(|it| self.profiles.get(it))
+            .map(|it| it.save_slot())
+            .unwrap_or(SHARED_SLOT)
+    }
+    /// Gives the profile a save of its own, or has it go back to using the shared one.
+    ///
+    /// An active profile keeps playing on its current save when it gets one of its own.
+    pub fn set_isolated_save(&mut self, profile: impl AsRef<str>, isolated: bool) -> Result<()> {
+        let entry = self
+            .profiles
+            .get(profile.as_ref())
+            .with_context(|| format!("Profile '{}' doesn't exist", profile.as_ref()))?;
+        if entry.isolated_save == isolated {
+            return Ok(());
+        }
+
+        if self.active_profile.as_deref() == Some(profile.as_ref()) {
+            let saves = ProfileSaves::new(self.save_dir()?);
+            match isolated {
+                // The other profiles still need the shared save that's in use right now
+                true => saves.copy_to(SHARED_SLOT)?,
+                false => saves.swap(&entry.id, SHARED_SLOT)?,
+            }
+        }
+
+        if let Some(entry) = self.profiles.get_mut(profile.as_ref()) {
+            entry.isolated_save = isolated;
+        }
+
+        Ok(())
+    }
+    /// Has the profile use a local copy of the workshop mod instead, see [`PinnedMod`].
+    pub fn pin_mod(
+        &mut self,
+        profile: impl AsRef<str>,
+        workshop_item_id: usize,
+    ) -> Result<PinnedMod> {
+        let workshop = self
+            .noita_path
+            .workshop()
+            .context("Couldn't find the game's workshop directory")?;
+        let local_mods = self
+            .noita_path
+            .local_mods()
+            .context("Couldn't find the game's mods directory")?;
+        let mut mod_list = self.profiles.get_profile(profile.as_ref())?;
+        let pinned_elsewhere = self.profiles.values().any(|it| {
+            it.pinned_mods
+                .iter()
+                .any(|it| it.workshop_item_id == workshop_item_id)
+        });
+
+        let pinned = PinnedMod::pin(
+            &mut mod_list,
+            workshop_item_id,
+            workshop,
+            local_mods,
+            pinned_elsewhere,
+        )?;
+        self.update_profile(profile.as_ref(), &mod_list)?;
+        if let Some(entry) = self.profiles.get_mut(profile.as_ref()) {
+            entry.pinned_mods.push(pinned.clone());
+        }
+
+        Ok(pinned)
+    }
+    /// Has the profile go back to using the workshop mod, `query` being either its name or workshop id.
+    ///
+    /// The local copy is moved to the [`ModTrash`] once no profile uses it anymore, if that fails it's left in place.
+    pub fn unpin_mod(
+        &mut self,
+        profile: impl AsRef<str>,
+        query: impl AsRef<str>,
+    ) -> Result<(PinnedMod, Option<TrashedMod>)> {
+        let entry = self
+            .profiles
+            .get(profile.as_ref())
+            .with_context(|| format!("Profile '{}' doesn't exist", profile.as_ref()))?;
+        let pinned = entry
+            .pinned_mods
+            .iter()
+            .find(|it| {
+                it.name == query.as_ref() || it.workshop_item_id.to_string() == query.as_ref()
+            })
+            .cloned()
+            .with_context(|| format!("No mod '{}' is pinned in the profile", query.as_ref()))?;
+
+        let mut mod_list = self.profiles.get_profile(profile.as_ref())?;
+        pinned.unpin(&mut mod_list)?;
+        self.update_profile(profile.as_ref(), &mod_list)?;
+        if let Some(entry) = self.profiles.get_mut(profile.as_ref()) {
+            entry.pinned_mods.retain(|it| *it != pinned);
+        }
+
+        let still_pinned = self
+            .profiles
+            .values()
+            .any(|it| it.pinned_mods.contains(&pinned));
+        let local_mods = self.noita_path.local_mods();
+        let copy = local_mods
+            .as_ref()
+            .map(|it| it.join(&pinned.name))
+            .filter(|it| it.is_dir());
+        // The profile is already unpinned at this point, a copy left behind is only taking up space
+        let trashed = match (copy, local_mods) {
+            (Some(copy), Some(local_mods)) if !still_pinned => ModTrash::default()
+                .trash(
+                    copy,
+                    local_mods,
+                    TrashRecord {
+                        name: pinned.name.clone(),
+                        title: pinned.name.clone(),
+                        profiles: vec![],
+                    },
+                )
+                .inspect_err(|err| warn!(%err, "Couldn't move the pinned copy to the trash"))
+                .ok(),
+            _ => None,
+        };
+
+        Ok((pinned, trashed))
+    }
+    fn save_dir(&self) -> Result<PathBuf> {
+        self.noita_path
+            .save_dir()
+            .context("Couldn't find Noita's save directory")
+    }
 }
@@ -14,3 +14,14 @@ pub static APP_CONFIG_PATH: LazyLock<PathBuf> =
     LazyLock::new(|| APP_CONFIG_DIR.join("config.toml"));
 
 pub static MOD_PROFILES_DIR: LazyLock<PathBuf> = LazyLock::new(|| APP_CONFIG_DIR.join("profiles"));
+
+pub static SAVE_SNAPSHOTS_DIR: LazyLock<PathBuf> =
+    LazyLock::new(|| APP_CONFIG_DIR.join("save_snapshots"));
+
+pub static SWITCH_SNAPSHOTS_DIR: LazyLock<PathBuf> =
+    LazyLock::new(|| APP_CONFIG_DIR.join("switch_snapshots"));
+
+pub static BISECT_STATE_PATH: LazyLock<PathBuf> =
+    LazyLock::new(|| APP_CONFIG_DIR.join("bisect.toml"));
+
+pub static MOD_TRASH_DIR: LazyLock<PathBuf> = LazyLock::new(|| APP_CONFIG_DIR.join("trash"));
@@ -1,6 +1,8 @@
-use std::path::Path;
+use std::{io::Write, path::Path};
 
-use color_eyre::eyre::{bail, Result};
+use color_eyre::eyre::{bail, ContextCompat, Result};
+use fs_err as fs;
+use walkdir::WalkDir;
 
 pub trait PathExt: AsRef<Path>
 where
@@ -18,6 +20,54 @@ where
             false => bail!("Path {:?} isn't a file", self.as_ref()),
         }
     }
+    /// Writes to a temporary file next to the path first, and only then moves it in place,
+    /// so that the path either has the old or the new contents even if interrupted midway.
+    fn write_atomic(&self, contents: impl AsRef<[u8]>) -> Result<()> {
+        let path = self.as_ref();
+        let file_name = path
+            .file_name()
+            .with_context(|| format!("Path {:?} has no file name", path))?;
+        let tmp_path = path.with_file_name(format!(".{}.tmp", file_name.to_string_lossy()));
+
+        let res = (|| {
+            let mut file = fs::File::create(&tmp_path)?;
+            file.write_all(contents.as_ref())?;
+            file.sync_all()?;
+            fs::rename(&tmp_path, path)
+        })();
+        if let Err(err) = res {
+            _ = fs::remove_file(&tmp_path);
+            return Err(err.into());
+        }
+
+        // Persist the rename itself
+        #[cfg(unix)]
+        if let Some(parent) = path.parent() {
+            _ = fs::File::open(parent).and_then(|it| it.sync_all());
+        }
+
+        Ok(())
+    }
+    /// Copies the directory's contents into `to`, leaving out entries for which `skip` returns true.
+    fn copy_dir(&self, to: impl AsRef<Path>, skip: impl Fn(&Path) -> bool) -> Result<()> {
+        let from = self.as_ref();
+        let to = to.as_ref();
+        for entry in WalkDir::new(from)
+            .follow_links(true)
+            .into_iter()
+            .filter_entry(|it| !skip(it.path()))
+        {
+            let entry = entry?;
+            let target = to.join(entry.path().strip_prefix(from)?);
+            if entry.file_type().is_dir() {
+                fs::create_dir_all(&target)?;
+            } else {
+                fs::copy(entry.path(), &target)?;
+            }
+        }
+
+        Ok(())
+    }
 }
 
 impl<T: AsRef<Path>> PathExt for T {}
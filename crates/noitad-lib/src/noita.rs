@@ -1,27 +1,128 @@
+pub mod bisect;
+pub mod dev_links;
+pub mod game_log;
+pub mod lua_audit;
+pub mod mod_catalog;
 pub mod mod_config;
+pub mod mod_hashes;
+pub mod mod_install;
+pub mod mod_scaffold;
+pub mod mod_trash;
+pub mod orphans;
+pub mod pinned_mods;
+pub mod process;
+pub mod profile_saves;
+pub mod save_snapshots;
+pub mod switch_snapshots;
+pub mod watcher;
 use fs_err as fs;
 
 use std::{
-    collections::HashMap,
-    io::Write,
+    collections::{BTreeSet, HashMap, HashSet},
     path::{Path, PathBuf},
 };
 
-use color_eyre::eyre::{bail, ContextCompat, Result};
-use mod_config::Mods;
-use serde::{Deserialize, Serialize};
+use color_eyre::eyre::{bail, Context, ContextCompat, Result};
+use itertools::Itertools;
+use mod_catalog::ModCatalog;
+use mod_config::{diff::ModsDiff, Mod, ModId, Mods};
+use mod_hashes::ModHash;
+use mod_trash::ModPlacement;
+use orphans::InstalledMods;
+use pinned_mods::PinnedMod;
+use serde::{Deserialize, Deserializer, Serialize};
+use tracing::info;
 use walkdir::WalkDir;
 
 use crate::{
     defines::{MOD_PROFILES_DIR, NOITA_STEAM_ID},
+    ext::PathExt,
     impl_deref_for_newtype,
 };
 
-/// HashMap of profile names and filepath to their mod_config file.
+/// Metadata of a mod profile, its mod list is stored separately under [`MOD_PROFILES_DIR`].
 #[derive(Debug, Clone, Default, Serialize, Deserialize)]
-pub struct ModProfiles(pub HashMap<String, PathBuf>);
+#[serde(default)]
+pub struct Profile {
+    /// Filesystem-safe identifier of the profile, stays the same across renames.
+    pub id: String,
+    /// Whether the profile keeps a save of its own instead of the one shared by the other profiles.
+    pub isolated_save: bool,
+    /// Mods requesting unrestricted API access that were approved to be enabled in the profile.
+    #[serde(skip_serializing_if = "BTreeSet::is_empty")]
+    pub approved_unrestricted: BTreeSet<ModId>,
+    /// Hashes of the profile's installed mods from when it was last saved, to tell which of them changed since.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub mod_hashes: Vec<ModHash>,
+    /// Workshop mods the profile uses a local copy of instead.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub pinned_mods: Vec<PinnedMod>,
+    /// Path of the profile's mod list in the older, name based layout.
+    #[serde(skip)]
+    legacy_path: Option<PathBuf>,
+}
+
+impl Profile {
+    pub fn path(&self) -> PathBuf {
+        MOD_PROFILES_DIR.join(format!("{}.xml", self.id))
+    }
+    /// Slot in [`profile_saves::ProfileSaves`] the profile's save is kept under while it's not in use.
+    pub fn save_slot(&self) -> &str {
+        match self.isolated_save {
+            true => &self.id,
+            false => profile_saves::SHARED_SLOT,
+        }
+    }
+    /// Enabled mods of `mod_list` that request unrestricted API access without being approved for it.
+    pub fn unapproved_mods(&self, mod_list: &Mods, catalog: &ModCatalog) -> Vec<ModId> {
+        catalog
+            .unrestricted_mods(mod_list)
+            .into_iter()
+            .filter(|it| it.enabled)
+            .map(|it| it.id())
+            .filter(|it| !self.approved_unrestricted.contains(it))
+            .collect()
+    }
+}
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum ProfileEntry {
+    Profile(Profile),
+    /// Profiles used to be stored as just the filepath to their mod_config file.
+    Legacy(PathBuf),
+}
+
+fn deserialize_profiles<'de, D: Deserializer<'de>>(
+    deserializer: D,
+) -> Result<HashMap<String, Profile>, D::Error> {
+    Ok(HashMap::<String, ProfileEntry>::deserialize(deserializer)?
+        .into_iter()
+        .map(|(name, entry)| {
+            let profile = match entry {
+                // An id is needed to tell where the mod list goes, one is derived on migration
+                ProfileEntry::Profile(profile) if profile.id.is_empty() => Profile {
+                    legacy_path: Some(profile.path()),
+                    ..profile
+                },
+                ProfileEntry::Profile(profile) => profile,
+                ProfileEntry::Legacy(path) => Profile {
+                    legacy_path: Some(path),
+                    ..Default::default()
+                },
+            };
+            (name, profile)
+        })
+        .collect())
+}
+
+/// HashMap of profile names and their metadata.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ModProfiles(
+    #[serde(deserialize_with = "deserialize_profiles")] pub HashMap<String, Profile>,
+);
 
-impl_deref_for_newtype!(ModProfiles, HashMap<String, PathBuf>);
+impl_deref_for_newtype!(ModProfiles, HashMap<String, Profile>);
 
 impl ModProfiles {
     pub fn add_profile(
@@ -34,35 +135,175 @@ impl ModProfiles {
         }
 
         let mod_list = Mods::from_noita(noita_save_dir.as_ref())?;
-        let path = self.write_profile(profile.as_ref(), &mod_list)?;
-        self.insert(profile.as_ref().into(), path);
+        let entry = Profile {
+            id: self.new_profile_id(profile.as_ref(), None),
+            ..Default::default()
+        };
+        ModProfiles::write_profile(&entry, &mod_list)?;
+        self.insert(profile.as_ref().into(), entry);
 
         Ok(mod_list)
     }
     pub fn get_profile(&self, profile: impl AsRef<str>) -> Result<Mods> {
-        let path = self
+        let entry = self
             .get(profile.as_ref())
             .with_context(|| format!("Profile '{}' not found.", profile.as_ref()))?;
 
-        Ok(quick_xml::de::from_str(&fs::read_to_string(path)?)?)
+        Mods::from_file(entry.path())
     }
     pub fn update_profile(&mut self, profile: impl AsRef<str>, mod_list: &Mods) -> Result<()> {
-        if self.get(profile.as_ref()).is_none() {
-            bail!("Profile '{}' doesn't exist", profile.as_ref())
-        }
-        self.write_profile(profile, mod_list)?;
+        let entry = self
+            .get(profile.as_ref())
+            .with_context(|| format!("Profile '{}' doesn't exist", profile.as_ref()))?;
+        ModProfiles::write_profile(entry, mod_list)?;
 
         Ok(())
     }
+    /// Remembers what the installed mods of `mod_list` look like right now, for [`ModProfiles::changed_mods`].
+    ///
+    /// This accepts any changes to the mods, [`ModProfiles::update_mod_hashes`] is what's used on save.
+    pub fn record_mod_hashes(
+        &mut self,
+        profile: impl AsRef<str>,
+        mod_list: &Mods,
+        installed: &InstalledMods,
+    ) -> Result<()> {
+        let entry = self
+            .get_mut(profile.as_ref())
+            .with_context(|| format!("Profile '{}' doesn't exist", profile.as_ref()))?;
+        entry.mod_hashes = mod_hashes::hash_mods(mod_list, installed)?;
+
+        Ok(())
+    }
+    /// Records hashes for mods added to `mod_list` and forgets the ones of removed mods, see [`mod_hashes::update_hashes`].
+    pub fn update_mod_hashes(
+        &mut self,
+        profile: impl AsRef<str>,
+        mod_list: &Mods,
+        installed: &InstalledMods,
+    ) -> Result<()> {
+        let entry = self
+            .get_mut(profile.as_ref())
+            .with_context(|| format!("Profile '{}' doesn't exist", profile.as_ref()))?;
+        entry.mod_hashes = mod_hashes::update_hashes(&entry.mod_hashes, mod_list, installed)?;
+
+        Ok(())
+    }
+    /// Mods of the profile whose files changed since it was last saved, like after a workshop update.
+    pub fn changed_mods(
+        &self,
+        profile: impl AsRef<str>,
+        installed: &InstalledMods,
+    ) -> Result<Vec<ModId>> {
+        let entry = self
+            .get(profile.as_ref())
+            .with_context(|| format!("Profile '{}' doesn't exist", profile.as_ref()))?;
+
+        mod_hashes::changed_mods(
+            &entry.mod_hashes,
+            &Mods::from_file(entry.path())?,
+            installed,
+        )
+    }
+    /// Removes the mod from the mod list of every profile, returning where it was in each of them.
+    pub fn remove_mod(&self, id: &ModId) -> Result<Vec<ModPlacement>> {
+        let mut removed = vec![];
+        for (profile, entry) in self.iter().sorted_by(|a, b| a.0.cmp(b.0)) {
+            let mut mod_list = Mods::from_file(entry.path())?;
+            let Some(position) = mod_list.position(id) else {
+                continue;
+            };
+            let mod_ = mod_list.mods.remove(position);
+            ModProfiles::write_profile(entry, &mod_list)?;
+            removed.push(ModPlacement {
+                profile: profile.to_owned(),
+                position,
+                enabled: mod_.enabled,
+            });
+        }
+
+        Ok(removed)
+    }
+    /// Puts the mod back where [`ModProfiles::remove_mod`] took it out of, returning the profiles it was put back into.
+    ///
+    /// Profiles that don't exist anymore or that have the mod again are skipped.
+    pub fn restore_mod(&self, id: &ModId, entries: &[ModPlacement]) -> Result<Vec<String>> {
+        let mut restored = vec![];
+        for it in entries {
+            let Some(entry) = self.get(&it.profile) else {
+                continue;
+            };
+            let mut mod_list = Mods::from_file(entry.path())?;
+            if mod_list.position(id).is_some() {
+                continue;
+            }
+            let mut mod_ = Mod::new(&id.name, id.workshop_item_id);
+            mod_.enabled = it.enabled;
+            mod_list
+                .mods
+                .insert(it.position.min(mod_list.mods.len()), mod_);
+            ModProfiles::write_profile(entry, &mod_list)?;
+            restored.push(it.profile.clone());
+        }
+
+        Ok(restored)
+    }
     pub fn remove_profile(&mut self, profile: impl AsRef<str>) -> Result<()> {
-        self.remove(profile.as_ref()).with_context(|| {
+        let entry = self.remove(profile.as_ref()).with_context(|| {
             format!(
                 "Profile '{}' does not exist and cannot be removed",
                 profile.as_ref()
             )
         })?;
 
-        fs::remove_file(ModProfiles::get_profile_file_path(profile))?;
+        fs::remove_file(entry.path())?;
+
+        Ok(())
+    }
+    /// Renames a profile, its mod list on disk is left untouched.
+    pub fn rename_profile(
+        &mut self,
+        profile: impl AsRef<str>,
+        new_name: impl AsRef<str>,
+    ) -> Result<()> {
+        if self.get(new_name.as_ref()).is_some() {
+            bail!("Profile '{}' already exists", new_name.as_ref())
+        }
+        let entry = self.remove(profile.as_ref()).with_context(|| {
+            format!(
+                "Profile '{}' does not exist and cannot be renamed",
+                profile.as_ref()
+            )
+        })?;
+        self.insert(new_name.as_ref().into(), entry);
+
+        Ok(())
+    }
+    /// Copies a profile's mod list and settings under a new name, the copy uses the shared save.
+    pub fn duplicate_profile(
+        &mut self,
+        profile: impl AsRef<str>,
+        new_name: impl AsRef<str>,
+    ) -> Result<()> {
+        if self.get(new_name.as_ref()).is_some() {
+            bail!("Profile '{}' already exists", new_name.as_ref())
+        }
+        let mut entry = self
+            .get(profile.as_ref())
+            .with_context(|| {
+                format!(
+                    "Profile '{}' does not exist and cannot be duplicated",
+                    profile.as_ref()
+                )
+            })?
+            .clone();
+        let path = entry.path();
+
+        entry.id = self.new_profile_id(new_name.as_ref(), None);
+        // The copy starts out on the shared save, the original's own save stays with it
+        entry.isolated_save = false;
+        fs::copy(path, entry.path())?;
+        self.insert(new_name.as_ref().into(), entry);
 
         Ok(())
     }
@@ -71,28 +312,153 @@ impl ModProfiles {
         profile: impl AsRef<str>,
         noita_save_dir: impl AsRef<Path>,
     ) -> Result<()> {
-        fs::copy(
-            self.get(profile.as_ref()).with_context(|| {
+        bisect::Bisect::ensure_not_running()?;
+        let path = self
+            .get(profile.as_ref())
+            .with_context(|| {
                 format!(
                     "Profile '{}' doesn't exist and cannot be switched to",
                     profile.as_ref()
                 )
-            })?,
-            noita_save_dir.as_ref().join("mod_config.xml"),
-        )?;
+            })?
+            .path();
 
-        Ok(())
+        mod_config::backups::write_mod_config(noita_save_dir, fs::read(path)?)
+    }
+    /// Brings the profile up to date with the game's current mod list.
+    ///
+    /// Returns whether the profile's mod list had to be changed. Nothing is synced while a
+    /// [`bisect::Bisect`] is in progress, as the game only has a part of the mods enabled then.
+    pub fn sync_profile_with_noita(
+        &self,
+        profile: impl AsRef<str>,
+        noita_save_dir: impl AsRef<Path>,
+    ) -> Result<bool> {
+        if bisect::Bisect::is_running() {
+            return Ok(false);
+        }
+        let entry = self
+            .get(profile.as_ref())
+            .with_context(|| format!("Profile '{}' doesn't exist", profile.as_ref()))?;
+
+        let mut mod_list = Mods::from_file(entry.path())?;
+        let old = mod_list.to_xml();
+        mod_list.sync_with_noita(noita_save_dir)?;
+        if mod_list.to_xml() == old {
+            return Ok(false);
+        }
+        ModProfiles::write_profile(entry, &mod_list)?;
+
+        Ok(true)
     }
-    fn get_profile_file_path(profile: impl AsRef<str>) -> PathBuf {
-        MOD_PROFILES_DIR.join(format!("{}.xml", profile.as_ref()))
+    /// Compares the profile against the game's current mod list, changes are relative to the profile.
+    pub fn diff_with_noita(
+        &self,
+        profile: impl AsRef<str>,
+        noita_save_dir: impl AsRef<Path>,
+    ) -> Result<ModsDiff> {
+        Ok(self
+            .get_profile(profile)?
+            .diff(&Mods::from_noita(noita_save_dir)?))
     }
-    fn write_profile(&mut self, profile: impl AsRef<str>, mod_list: &Mods) -> Result<PathBuf> {
+    /// Moves profiles from the older layout, where mod lists were stored under the profile's name,
+    /// over to id based files.
+    ///
+    /// Returns whether anything was migrated, in which case the config needs to be stored again.
+    pub fn migrate(&mut self) -> Result<bool> {
+        let legacy = self
+            .iter()
+            .filter(|(_, entry)| entry.legacy_path.is_some())
+            .map(|(name, _)| name.to_owned())
+            .sorted()
+            .collect_vec();
+
+        for name in legacy.iter() {
+            let mut entry = self
+                .remove(name)
+                .expect("name was taken from the profiles list");
+            let legacy_path = entry.legacy_path.take().expect("filtered on legacy_path");
+            let reusable = match legacy_path.exists() {
+                true => legacy_path.clone(),
+                // A migration that failed partway might've moved it already
+                false => MOD_PROFILES_DIR.join(format!("{}.xml", Self::profile_slug(name))),
+            };
+            entry.id = self.new_profile_id(name, Some(&reusable));
+
+            let path = entry.path();
+            info!(?legacy_path, ?path, "Migrating profile");
+            // Otherwise it's either been moved already or is missing, and loading the profile fails
+            // just as it did before
+            if legacy_path != path && legacy_path.exists() {
+                fs::create_dir_all(MOD_PROFILES_DIR.as_path())?;
+                // Failing here leaves the config as it was on disk, as it's only stored after migrating
+                fs::rename(&legacy_path, &path)
+                    .with_context(|| format!("Couldn't move the mod list of profile '{}'", name))?;
+            }
+            self.insert(name.to_owned(), entry);
+        }
+
+        Ok(!legacy.is_empty())
+    }
+    /// Generates a filesystem-safe identifier from the profile name that isn't used by any other profile.
+    ///
+    /// `reusable` is a profile file that may be taken over by the new identifier.
+    fn new_profile_id(&self, profile: &str, reusable: Option<&Path>) -> String {
+        let slug = Self::profile_slug(profile);
+        let taken = self
+            .values()
+            .map(|it| it.id.as_str())
+            .chain(
+                self.values()
+                    .filter_map(|it| it.legacy_path.as_ref())
+                    .filter_map(|it| it.file_stem())
+                    .filter_map(|it| it.to_str()),
+            )
+            .collect::<HashSet<_>>();
+
+        let mut id = slug.clone();
+        let mut n = 2;
+        loop {
+            let path = MOD_PROFILES_DIR.join(format!("{id}.xml"));
+            if !taken.contains(id.as_str()) && (!path.exists() || Some(path.as_path()) == reusable)
+            {
+                break id;
+            }
+            id = format!("{slug}-{n}");
+            n += 1;
+        }
+    }
+    /// Filesystem-safe form of the profile name, which identifiers are derived from.
+    fn profile_slug(profile: &str) -> String {
+        let mut slug = String::new();
+        for c in profile.chars() {
+            if c.is_ascii_alphanumeric() {
+                slug.push(c.to_ascii_lowercase());
+            } else if !slug.is_empty() && !slug.ends_with('-') {
+                slug.push('-');
+            }
+        }
+        let mut slug = slug
+            .trim_end_matches('-')
+            .chars()
+            .take(48)
+            .collect::<String>();
+        // Device names that Windows won't allow as a file name
+        let is_reserved = matches!(slug.as_str(), "con" | "prn" | "aux" | "nul")
+            || (slug.len() == 4
+                && (slug.starts_with("com") || slug.starts_with("lpt"))
+                && slug.ends_with(|c: char| c.is_ascii_digit()));
+        if slug.is_empty() || is_reserved {
+            slug = format!("profile-{slug}").trim_end_matches('-').to_owned();
+        }
+
+        slug
+    }
+    fn write_profile(profile: &Profile, mod_list: &Mods) -> Result<()> {
         fs::create_dir_all(MOD_PROFILES_DIR.as_path())?;
-        let path = ModProfiles::get_profile_file_path(profile);
-        fs::File::create(&path)?
-            .write_fmt(format_args!("{}", quick_xml::se::to_string(mod_list)?))?;
+        profile.path().write_atomic(mod_list.to_xml())?;
 
-        Ok(path)
+        Ok(())
     }
 }
 
@@ -126,31 +492,26 @@ impl NoitaPath {
         match self {
             NoitaPath::Steam => steamlocate::SteamDir::locate()
                 .as_mut()
-                .map(|it| it.app(&NOITA_STEAM_ID).map(|it| it.path.clone()))
-                .flatten(),
+                .and_then(|it| it.app(&NOITA_STEAM_ID).map(|it| it.path.clone())),
             NoitaPath::Other(game_path) => game_path.as_ref().map(|it| it.game_root.clone()),
         }
     }
     pub fn save_dir(&self) -> Option<PathBuf> {
         let appdata_part = "AppData/LocalLow/Nolla_Games_Noita/save00";
         match self {
-            NoitaPath::Steam => steamlocate::SteamDir::locate()
-                .as_mut()
-                .map(|it| {
-                    it.libraryfolders()
-                        .paths
-                        .iter()
-                        .map(|library: &PathBuf| {
-                            library
-                                .join("compatdata")
-                                .join(&NOITA_STEAM_ID.to_string())
-                                .join("pfx/drive_c/users/steamuser")
-                                .join(appdata_part)
-                        })
-                        .filter(|it| it.is_dir())
-                        .next()
-                })
-                .flatten(),
+            NoitaPath::Steam => steamlocate::SteamDir::locate().as_mut().and_then(|it| {
+                it.libraryfolders()
+                    .paths
+                    .iter()
+                    .map(|library: &PathBuf| {
+                        library
+                            .join("compatdata")
+                            .join(NOITA_STEAM_ID.to_string())
+                            .join("pfx/drive_c/users/steamuser")
+                            .join(appdata_part)
+                    })
+                    .find(|it| it.is_dir())
+            }),
             NoitaPath::Other(game_path) => {
                 if cfg!(target_os = "windows") {
                     directories::UserDirs::new()
@@ -159,9 +520,8 @@ impl NoitaPath {
                 } else if cfg!(target_os = "linux") {
                     game_path
                         .as_ref()
-                        .map(|it| it.wine_prefix.clone())
-                        .flatten()
-                        .map(|path| {
+                        .and_then(|it| it.wine_prefix.clone())
+                        .and_then(|path| {
                             WalkDir::new(path.join("drive_c/users"))
                                 .follow_links(true)
                                 .max_depth(1)
@@ -172,7 +532,6 @@ impl NoitaPath {
                                 })
                                 .find_map(|it| it.map(|e| e.into_path()).ok())
                         })
-                        .flatten()
                         .map(|p| p.join(appdata_part))
                         .filter(|p| p.is_dir())
                 } else {
@@ -183,21 +542,17 @@ impl NoitaPath {
     }
     pub fn workshop(&self) -> Option<PathBuf> {
         match self {
-            NoitaPath::Steam => steamlocate::SteamDir::locate()
-                .as_mut()
-                .map(|it| {
-                    it.libraryfolders()
-                        .paths
-                        .iter()
-                        .map(|library| {
-                            library
-                                .join("workshop/content")
-                                .join(&NOITA_STEAM_ID.to_string())
-                        })
-                        .filter(|path| path.is_dir())
-                        .next()
-                })
-                .flatten(),
+            NoitaPath::Steam => steamlocate::SteamDir::locate().as_mut().and_then(|it| {
+                it.libraryfolders()
+                    .paths
+                    .iter()
+                    .map(|library| {
+                        library
+                            .join("workshop/content")
+                            .join(NOITA_STEAM_ID.to_string())
+                    })
+                    .find(|path| path.is_dir())
+            }),
             _ => None,
         }
     }
@@ -205,3 +560,119 @@ impl NoitaPath {
         self.game_root().map(|p| p.join("mods"))
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Points the app's directories at a temporary one, shared by the tests that need files.
+    ///
+    /// The config directory can only be moved through the environment on unix.
+    #[cfg(unix)]
+    fn profiles_dir() -> &'static Path {
+        static DIR: std::sync::LazyLock<PathBuf> = std::sync::LazyLock::new(|| {
+            let home = std::env::temp_dir().join(format!("noitad-test-{}", std::process::id()));
+            std::env::set_var("HOME", &home);
+            std::env::set_var("XDG_CONFIG_HOME", home.join(".config"));
+            // Never touch the real profiles
+            assert!(MOD_PROFILES_DIR.starts_with(&home));
+            fs::create_dir_all(MOD_PROFILES_DIR.as_path()).unwrap();
+            MOD_PROFILES_DIR.clone()
+        });
+        &DIR
+    }
+
+    #[cfg(unix)]
+    fn legacy_profiles(names: &[&str]) -> ModProfiles {
+        let mut profiles = ModProfiles::default();
+        for name in names {
+            let path = profiles_dir().join(format!("{name}.xml"));
+            fs::write(&path, format!("<Mods><Mod name=\"{name}\" /></Mods>")).unwrap();
+            let entry = Profile {
+                legacy_path: Some(path),
+                ..Default::default()
+            };
+            profiles.0.insert(name.to_string(), entry);
+        }
+        profiles
+    }
+
+    #[test]
+    fn profile_slugs_are_filesystem_safe() {
+        assert_eq!(ModProfiles::profile_slug("My Profile!"), "my-profile");
+        assert_eq!(ModProfiles::profile_slug("--a  b--"), "a-b");
+        assert_eq!(ModProfiles::profile_slug("Ñoño"), "o-o");
+        assert_eq!(ModProfiles::profile_slug("!!!"), "profile");
+        assert_eq!(ModProfiles::profile_slug("CON"), "profile-con");
+        assert_eq!(ModProfiles::profile_slug("lpt1"), "profile-lpt1");
+        assert_eq!(ModProfiles::profile_slug("com10"), "com10");
+        assert_eq!(ModProfiles::profile_slug(&"x".repeat(60)).len(), 48);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn legacy_entries_are_marked_for_migration() {
+        // Entries without an id point at the profiles directory
+        profiles_dir();
+        let profiles: ModProfiles = toml::from_str(
+            r#"
+            old = "/profiles/old.xml"
+            new = { id = "new" }
+            no-id = { isolated_save = true }
+            "#,
+        )
+        .unwrap();
+
+        assert_eq!(
+            profiles["old"].legacy_path.as_deref(),
+            Some(Path::new("/profiles/old.xml"))
+        );
+        assert_eq!(profiles["new"].legacy_path, None);
+        assert!(profiles["no-id"].legacy_path.is_some());
+        assert!(profiles["no-id"].isolated_save);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn migrate_moves_mod_lists_to_id_based_files() {
+        let mut profiles = legacy_profiles(&["Migrate Me"]);
+        assert!(profiles.migrate().unwrap());
+
+        let entry = &profiles["Migrate Me"];
+        assert_eq!(entry.id, "migrate-me");
+        assert_eq!(entry.legacy_path, None);
+        assert!(!profiles_dir().join("Migrate Me.xml").exists());
+        assert_eq!(
+            profiles.get_profile("Migrate Me").unwrap().mods[0].name,
+            "Migrate Me"
+        );
+        assert!(!profiles.migrate().unwrap());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn migrate_gives_profiles_with_the_same_slug_distinct_ids() {
+        let mut profiles = legacy_profiles(&["Same Slug", "same slug"]);
+        profiles.migrate().unwrap();
+
+        // Migrated in order of their names
+        assert_eq!(profiles["Same Slug"].id, "same-slug");
+        assert_eq!(profiles["same slug"].id, "same-slug-2");
+        assert_eq!(
+            profiles.get_profile("same slug").unwrap().mods[0].name,
+            "same slug"
+        );
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn migrate_reuses_a_file_moved_by_an_interrupted_migration() {
+        let mut profiles = legacy_profiles(&["Interrupted"]);
+        let moved = profiles_dir().join("interrupted.xml");
+        fs::rename(profiles_dir().join("Interrupted.xml"), &moved).unwrap();
+
+        profiles.migrate().unwrap();
+        assert_eq!(profiles["Interrupted"].id, "interrupted");
+        assert!(moved.is_file());
+    }
+}
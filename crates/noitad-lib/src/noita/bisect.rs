@@ -0,0 +1,305 @@
+use std::{collections::HashSet, path::Path};
+
+use color_eyre::eyre::{self, bail, Result};
+use fs_err as fs;
+use serde::{Deserialize, Serialize};
+use tracing::info;
+
+use crate::defines::BISECT_STATE_PATH;
+
+use super::{
+    mod_config::{ModId, Mods},
+    process::NoitaProcess,
+};
+
+/// Outcome of running the game with the mods of the current step.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Verdict {
+    /// The problem didn't show up.
+    Good,
+    /// The problem showed up.
+    Bad,
+}
+
+/// Narrows down the enabled mods of a profile to the ones a problem needs to show up.
+///
+/// Each step the game is run with a part of the mods enabled, and told whether the problem showed up.
+/// Progress is kept in [`BISECT_STATE_PATH`], as it spans several runs of the game.
+///
+/// The search looks for the shortest prefix of the suspected mods, in load order, that still
+/// breaks the game. The last mod of that prefix is needed for the problem, the mods after it are
+/// not, and the search is repeated on the mods before it until the ones found so far break the
+/// game on their own.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Bisect {
+    pub profile: String,
+    /// Mods still suspected, in load order.
+    candidates: Vec<ModId>,
+    /// Mods found to be needed for the problem, in load order.
+    culprits: Vec<ModId>,
+    /// Largest number of candidates known to work alongside the culprits, `None` if not even the
+    /// culprits on their own are known to work.
+    good: Option<usize>,
+    /// Smallest number of candidates known to break the game alongside the culprits.
+    bad: usize,
+}
+
+impl Bisect {
+    /// The bisect in progress, if there is one.
+    pub fn load() -> Result<Option<Self>> {
+        if !Self::is_running() {
+            return Ok(None);
+        }
+        Ok(Some(
+            confy::load_path(BISECT_STATE_PATH.as_path()).map_err(eyre::Report::msg)?,
+        ))
+    }
+    pub fn is_running() -> bool {
+        BISECT_STATE_PATH.is_file()
+    }
+    /// Fails while a bisect is in progress, for anything else that writes the game's mod list.
+    pub fn ensure_not_running() -> Result<()> {
+        if Self::is_running() {
+            bail!("A bisect is in progress, finish or reset it first")
+        }
+        Ok(())
+    }
+    /// Starts bisecting the enabled mods of `mod_list`, the mod list of `profile`, which is
+    /// assumed to break the game, and writes the mods of the first step to the game.
+    pub fn start(
+        profile: impl AsRef<str>,
+        mod_list: &Mods,
+        noita_save_dir: impl AsRef<Path>,
+    ) -> Result<Self> {
+        if Self::is_running() {
+            bail!("A bisect is already in progress, reset it first")
+        }
+        let candidates = mod_list
+            .mods
+            .iter()
+            .filter(|it| it.enabled)
+            .map(|it| it.id())
+            .collect::<Vec<_>>();
+        if candidates.len() < 2 {
+            bail!(
+                "Profile '{}' needs at least 2 enabled mods to bisect",
+                profile.as_ref()
+            )
+        }
+
+        let bisect = Self {
+            profile: profile.as_ref().to_owned(),
+            culprits: vec![],
+            // The game is assumed to work without any mods
+            good: Some(0),
+            bad: candidates.len(),
+            candidates,
+        };
+        bisect.write_step(mod_list, noita_save_dir)?;
+        bisect.store()?;
+        info!(profile = bisect.profile, "Started bisect");
+
+        Ok(bisect)
+    }
+    /// Records how the current step went and writes the mods of the next one to the game.
+    ///
+    /// Once the culprits are found, the bisect ends, `mod_list` is written back to the game
+    /// as is and the culprits are returned. The bisect is left as it was if this fails, so that
+    /// the step can be answered again.
+    pub fn answer(
+        &mut self,
+        verdict: Verdict,
+        mod_list: &Mods,
+        noita_save_dir: impl AsRef<Path>,
+    ) -> Result<Option<Vec<ModId>>> {
+        ensure_noita_closed()?;
+
+        let mut next = self.clone();
+        next.record(verdict);
+
+        if next.is_finished() {
+            let culprits = next.culprits.clone();
+            next.reset(mod_list, noita_save_dir)?;
+            *self = next;
+            info!(?culprits, "Finished bisect");
+            return Ok(Some(culprits));
+        }
+        next.write_step(mod_list, noita_save_dir)?;
+        next.store()?;
+        *self = next;
+
+        Ok(None)
+    }
+    /// Ends the bisect, writing `mod_list` back to the game as is.
+    pub fn reset(&self, mod_list: &Mods, noita_save_dir: impl AsRef<Path>) -> Result<()> {
+        ensure_noita_closed()?;
+        mod_list.overwrite_noita_mod_list(noita_save_dir)?;
+        fs::remove_file(BISECT_STATE_PATH.as_path())?;
+
+        Ok(())
+    }
+    /// Mods enabled for the current step, in load order.
+    pub fn step_mods(&self) -> Vec<ModId> {
+        let count = self.step_count().unwrap_or_default();
+        // Culprits are found from the back, so the candidates left all come before them
+        self.candidates[..count]
+            .iter()
+            .chain(self.culprits.iter())
+            .cloned()
+            .collect()
+    }
+    /// Mods found to be needed for the problem so far.
+    pub fn culprits(&self) -> &[ModId] {
+        &self.culprits
+    }
+    /// Number of mods still suspected, besides the culprits found so far.
+    pub fn suspects(&self) -> usize {
+        self.candidates.len()
+    }
+    /// Steps left until the next culprit is found.
+    pub fn steps_left(&self) -> usize {
+        (usize::BITS - (self.bad - self.lower()).leading_zeros()) as usize
+    }
+    pub fn is_finished(&self) -> bool {
+        self.step_count().is_none()
+    }
+    /// Smallest number of candidates that hasn't been ruled out as working.
+    fn lower(&self) -> usize {
+        self.good.map_or(0, |it| it + 1)
+    }
+    /// Number of candidates enabled in the current step.
+    fn step_count(&self) -> Option<usize> {
+        (self.lower() < self.bad).then(|| (self.lower() + self.bad) / 2)
+    }
+    /// Narrows down the candidates by how the current step went.
+    fn record(&mut self, verdict: Verdict) {
+        let count = self.step_count().unwrap_or_default();
+        match verdict {
+            Verdict::Good => self.good = Some(count),
+            Verdict::Bad => self.bad = count,
+        }
+        self.settle();
+    }
+    /// Moves on to the mods before a found culprit, until there's something left to test.
+    fn settle(&mut self) {
+        while self.lower() >= self.bad && self.bad > 0 {
+            let culprit = self.candidates[self.bad - 1].clone();
+            self.candidates.truncate(self.bad - 1);
+            self.culprits.insert(0, culprit);
+
+            // The culprits on their own haven't been tried yet
+            self.good = None;
+            self.bad = self.candidates.len();
+        }
+    }
+    fn write_step(&self, mod_list: &Mods, noita_save_dir: impl AsRef<Path>) -> Result<()> {
+        ensure_noita_closed()?;
+        let enabled = self.step_mods().into_iter().collect::<HashSet<_>>();
+        let mut mod_list = mod_list.clone();
+        for mod_ in mod_list.mods.iter_mut() {
+            mod_.enabled = enabled.contains(&mod_.id());
+        }
+        mod_list.overwrite_noita_mod_list(noita_save_dir)
+    }
+    fn store(&self) -> Result<()> {
+        confy::store_path(BISECT_STATE_PATH.as_path(), self)?;
+        Ok(())
+    }
+}
+
+fn ensure_noita_closed() -> Result<()> {
+    if let Some(process) = NoitaProcess::find() {
+        bail!(
+            "Noita is running (pid {}), close the game before changing its mod list",
+            process.pid
+        )
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::noita::mod_config::Mod;
+
+    fn ids(names: &[&str]) -> Vec<ModId> {
+        names.iter().map(|it| Mod::new(*it, 0).id()).collect()
+    }
+
+    fn new_bisect(candidates: &[&str]) -> Bisect {
+        Bisect {
+            profile: "test".into(),
+            candidates: ids(candidates),
+            culprits: vec![],
+            good: Some(0),
+            bad: candidates.len(),
+        }
+    }
+
+    /// Answers every step as the game would if it only broke with all of `needed` enabled,
+    /// returning the culprits and the number of steps it took.
+    fn run(mut bisect: Bisect, needed: &[&str]) -> (Vec<ModId>, usize) {
+        let needed = ids(needed);
+        let mut steps = 0;
+        while !bisect.is_finished() {
+            let enabled = bisect.step_mods();
+            let verdict = match needed.iter().all(|it| enabled.contains(it)) {
+                true => Verdict::Bad,
+                false => Verdict::Good,
+            };
+            bisect.record(verdict);
+            steps += 1;
+            assert!(steps <= 64, "bisect doesn't finish");
+        }
+        (bisect.culprits, steps)
+    }
+
+    const MODS: [&str; 8] = ["a", "b", "c", "d", "e", "f", "g", "h"];
+
+    #[test]
+    fn first_step_enables_half_of_the_mods() {
+        let bisect = new_bisect(&MODS);
+        assert_eq!(bisect.step_mods(), ids(&["a", "b", "c", "d"]));
+        assert_eq!(bisect.steps_left(), 3);
+        assert_eq!(bisect.suspects(), 8);
+    }
+
+    #[test]
+    fn finds_a_single_culprit_anywhere() {
+        for culprit in MODS {
+            let (culprits, steps) = run(new_bisect(&MODS), &[culprit]);
+            assert_eq!(culprits, ids(&[culprit]), "culprit {culprit}");
+            // Finding it and then ruling out the mods before it take about log2(8) steps each
+            assert!(steps <= 6, "{steps} steps for culprit {culprit}");
+        }
+    }
+
+    #[test]
+    fn finds_mods_that_only_break_together() {
+        let (culprits, _) = run(new_bisect(&MODS), &["b", "f"]);
+        assert_eq!(culprits, ids(&["b", "f"]));
+
+        let (culprits, _) = run(new_bisect(&MODS), &["a", "d", "h"]);
+        assert_eq!(culprits, ids(&["a", "d", "h"]));
+    }
+
+    #[test]
+    fn step_mods_are_in_load_order() {
+        let mut bisect = new_bisect(&MODS);
+        // "h" turns out to be needed, the rest is bisected alongside it
+        bisect.record(Verdict::Good);
+        bisect.record(Verdict::Good);
+        bisect.record(Verdict::Good);
+        assert_eq!(bisect.culprits(), ids(&["h"]));
+        assert!(!bisect.is_finished());
+
+        let step = bisect.step_mods();
+        assert_eq!(step.last(), ids(&["h"]).last());
+        let positions = step
+            .iter()
+            .map(|it| MODS.iter().position(|name| *name == it.name).unwrap())
+            .collect::<Vec<_>>();
+        assert!(positions.is_sorted(), "{positions:?}");
+    }
+}
@@ -0,0 +1,127 @@
+use std::{
+    collections::BTreeMap,
+    path::{Path, PathBuf},
+};
+
+use color_eyre::eyre::{bail, Context, ContextCompat, Result};
+use fs_err as fs;
+use serde::{Deserialize, Serialize};
+use tracing::info;
+
+use crate::{ext::PathExt, impl_deref_for_newtype};
+
+use super::{mod_catalog::ModInfo, mod_config::ModId};
+
+/// Mod under development, linked into the game's mods directory from where it's worked on.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct LinkedMod {
+    /// Directory the mod is developed in.
+    pub source: PathBuf,
+    /// Whether the mod was copied over instead of symlinked, copies have to be linked again to pick up changes.
+    pub copied: bool,
+}
+
+/// Linked mods, keyed by their folder name under `mods/`.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct LinkedMods(pub BTreeMap<String, LinkedMod>);
+
+impl_deref_for_newtype!(LinkedMods, BTreeMap<String, LinkedMod>);
+
+impl LinkedMods {
+    /// Links the mod in `source` into `local_mods` under the name of its folder.
+    ///
+    /// Symlinks are used where the platform has them, unless `copy` is set, e.g. for a sandboxed
+    /// game that can't see outside of its own directories. Linking an already linked mod again
+    /// replaces its link, which is how copies are refreshed.
+    pub fn link(
+        &mut self,
+        source: impl AsRef<Path>,
+        local_mods: impl AsRef<Path>,
+        copy: bool,
+    ) -> Result<ModInfo> {
+        let source = fs::canonicalize(source.as_ref())?;
+        let local_mods = local_mods.as_ref();
+        let name = source
+            .file_name()
+            .map(|it| it.to_string_lossy().into_owned())
+            .context("Mod directory has no name")?;
+        if local_mods
+            .canonicalize()
+            .is_ok_and(|it| source.starts_with(it))
+        {
+            bail!("{:?} is already in the game's mods directory", source)
+        }
+        let info = ModInfo::from_dir(
+            &source,
+            ModId {
+                name: name.clone(),
+                workshop_item_id: 0,
+            },
+        )
+        .with_context(|| format!("{:?} doesn't contain a valid mod", source))?;
+
+        let target = local_mods.join(&name);
+        if target.symlink_metadata().is_ok() {
+            if !self.contains_key(&name) {
+                bail!(
+                    "A mod named '{}' is already installed at {:?}",
+                    name,
+                    target
+                )
+            }
+            remove_link(&target)?;
+        }
+
+        let copied = copy || !cfg!(unix);
+        match copied {
+            true => {
+                if let Err(err) = source.copy_dir(&target, |it| it.ends_with(".git")) {
+                    _ = fs::remove_dir_all(&target);
+                    return Err(err);
+                }
+            }
+            #[cfg(unix)]
+            false => fs::os::unix::fs::symlink(&source, &target)?,
+            #[cfg(not(unix))]
+            false => unreachable!(),
+        }
+        info!(?source, ?target, copied, "Linked mod");
+        self.insert(name, LinkedMod { source, copied });
+
+        Ok(info)
+    }
+    /// Removes the mod's link from `local_mods`, its source is left untouched.
+    pub fn unlink(&mut self, name: &str, local_mods: impl AsRef<Path>) -> Result<LinkedMod> {
+        let linked = self
+            .remove(name)
+            .with_context(|| format!("Mod '{}' isn't linked", name))?;
+        let target = local_mods.as_ref().join(name);
+        if target.symlink_metadata().is_ok() {
+            remove_link(&target)?;
+        }
+        info!(name, "Unlinked mod");
+
+        Ok(linked)
+    }
+    /// Whether the mod's link in `local_mods` is still there and points to its source.
+    pub fn is_intact(&self, name: &str, local_mods: impl AsRef<Path>) -> bool {
+        let Some(linked) = self.get(name) else {
+            return false;
+        };
+        let target = local_mods.as_ref().join(name);
+        match linked.copied {
+            true => target.is_dir(),
+            false => fs::read_link(&target).is_ok_and(|it| it == linked.source),
+        }
+    }
+}
+
+fn remove_link(target: &Path) -> Result<()> {
+    match target.symlink_metadata()?.is_symlink() {
+        true => fs::remove_file(target)?,
+        false => fs::remove_dir_all(target)?,
+    }
+
+    Ok(())
+}
@@ -0,0 +1,185 @@
+use std::{
+    collections::HashMap,
+    path::{Path, PathBuf},
+};
+
+use color_eyre::eyre::Result;
+use fs_err as fs;
+use itertools::Itertools;
+use serde::Serialize;
+
+use crate::defines::NOITA_STEAM_ID;
+
+use super::mod_config::{ModId, Mods};
+
+pub const LOG_NAME: &str = "logger.txt";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Severity {
+    Warning,
+    Error,
+}
+
+/// Mod a log message refers to, as far as can be told from the paths in it.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize)]
+pub enum ModRef {
+    /// Mods are mounted under `mods/<name>/`.
+    Name(String),
+    /// Workshop items are at times referred to by their folder in the workshop instead.
+    Workshop(usize),
+}
+
+/// Log message, along with how many times it was repeated.
+#[derive(Debug, Clone, Serialize)]
+pub struct LogEntry {
+    pub severity: Severity,
+    pub message: String,
+    /// Line of the first occurrence, starting at 1.
+    pub line: usize,
+    pub count: usize,
+    #[serde(skip)]
+    pub mod_ref: Option<ModRef>,
+}
+
+/// Errors and warnings from the game's `logger.txt`.
+#[derive(Debug, Clone, Default)]
+pub struct GameLog {
+    pub entries: Vec<LogEntry>,
+}
+
+/// Log entries of a single mod.
+#[derive(Debug, Clone, Serialize)]
+pub struct ModLogReport {
+    pub id: ModId,
+    pub entries: Vec<LogEntry>,
+}
+
+impl ModLogReport {
+    pub fn count(&self, severity: Severity) -> usize {
+        self.entries
+            .iter()
+            .filter(|it| it.severity == severity)
+            .map(|it| it.count)
+            .sum()
+    }
+}
+
+/// Log entries grouped by the mods of a profile they belong to.
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct LogSummary {
+    /// Mods with entries, in load order.
+    pub mods: Vec<ModLogReport>,
+    /// Entries that couldn't be tied to a mod of the profile.
+    pub unattributed: Vec<LogEntry>,
+}
+
+impl GameLog {
+    pub fn path(game_root: impl AsRef<Path>) -> PathBuf {
+        game_root.as_ref().join(LOG_NAME)
+    }
+    /// Reads the log the game left behind in its root directory.
+    pub fn from_game_root(game_root: impl AsRef<Path>) -> Result<Self> {
+        Self::from_file(Self::path(game_root))
+    }
+    pub fn from_file(path: impl AsRef<Path>) -> Result<Self> {
+        // Mods are free to log whatever they want, it isn't necessarily valid UTF-8
+        Ok(Self::parse(&String::from_utf8_lossy(&fs::read(path)?)))
+    }
+    pub fn parse(log: &str) -> Self {
+        let mut entries: Vec<LogEntry> = vec![];
+        let mut seen = HashMap::<_, usize>::new();
+        for (i, line) in log.lines().enumerate() {
+            let message = line.trim();
+            let Some(severity) = severity(message) else {
+                continue;
+            };
+            // Errors from scripts running every frame tend to be repeated a lot
+            match seen.get(&(severity, message)) {
+                Some(&index) => entries[index].count += 1,
+                None => {
+                    seen.insert((severity, message), entries.len());
+                    entries.push(LogEntry {
+                        severity,
+                        message: message.to_owned(),
+                        line: i + 1,
+                        count: 1,
+                        mod_ref: find_mod_ref(message),
+                    });
+                }
+            }
+        }
+
+        Self { entries }
+    }
+    /// Ties the entries to the mods of `mod_list`.
+    pub fn summarize(&self, mod_list: &Mods) -> LogSummary {
+        let mut summary = LogSummary::default();
+        let mut by_mod = vec![vec![]; mod_list.mods.len()];
+        for entry in self.entries.iter() {
+            let position = entry.mod_ref.as_ref().and_then(|mod_ref| {
+                mod_list.mods.iter().position(|it| match mod_ref {
+                    ModRef::Name(name) => it.name == *name,
+                    ModRef::Workshop(id) => it.workshop_item_id == *id,
+                })
+            });
+            match position {
+                Some(i) => by_mod[i].push(entry.clone()),
+                None => summary.unattributed.push(entry.clone()),
+            }
+        }
+
+        summary.mods = mod_list
+            .mods
+            .iter()
+            .zip(by_mod)
+            .filter(|(_, entries)| !entries.is_empty())
+            .map(|(mod_, entries)| ModLogReport {
+                id: mod_.id(),
+                entries,
+            })
+            .collect_vec();
+
+        summary
+    }
+}
+
+fn severity(message: &str) -> Option<Severity> {
+    let lowercase = message.to_lowercase();
+    if lowercase.contains("error") {
+        Some(Severity::Error)
+    } else if lowercase.contains("warning") {
+        Some(Severity::Warning)
+    } else {
+        None
+    }
+}
+
+fn find_mod_ref(message: &str) -> Option<ModRef> {
+    let workshop_dir = format!("{}/", NOITA_STEAM_ID);
+    let message = message.replace('\\', "/");
+    if let Some((_, rest)) = message.split_once(&workshop_dir) {
+        let id = rest
+            .chars()
+            .take_while(|it| it.is_ascii_digit())
+            .collect::<String>();
+        if let Ok(id) = id.parse() {
+            return Some(ModRef::Workshop(id));
+        }
+    }
+
+    message.match_indices("mods/").find_map(|(i, _)| {
+        // Skips over things like `some_mods/`
+        if message[..i]
+            .chars()
+            .next_back()
+            .is_some_and(|it| it.is_alphanumeric() || it == '_')
+        {
+            return None;
+        }
+        let (name, _) = message[i + "mods/".len()..].split_once('/')?;
+        Some(name)
+            .filter(|it| !it.is_empty() && !it.contains(char::is_whitespace))
+            .map(|it| ModRef::Name(it.to_owned()))
+    })
+}
@@ -0,0 +1,168 @@
+use std::{fmt, path::PathBuf};
+
+use color_eyre::eyre::Result;
+use fs_err as fs;
+use itertools::Itertools;
+use serde::Serialize;
+use walkdir::WalkDir;
+
+use super::{
+    mod_catalog::{ModCatalog, ModInfo},
+    mod_config::{ModId, Mods},
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Risk {
+    Low,
+    Medium,
+    High,
+}
+
+impl fmt::Display for Risk {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Risk::Low => "low",
+            Risk::Medium => "medium",
+            Risk::High => "high",
+        })
+    }
+}
+
+/// Calls looked for, along with how risky they are and what they allow a mod to do.
+///
+/// Most of these are only available to mods with unrestricted API access, but a mod asking for
+/// it doesn't tell what it does with it.
+const DANGEROUS_CALLS: [(&str, Risk, &str); 10] = [
+    ("io.popen", Risk::High, "runs shell commands"),
+    ("os.execute", Risk::High, "runs shell commands"),
+    ("package.loadlib", Risk::High, "loads native libraries"),
+    ("ffi", Risk::High, "calls native code through LuaJIT's FFI"),
+    ("io.open", Risk::Medium, "reads or writes files"),
+    ("io.lines", Risk::Medium, "reads files"),
+    ("os.remove", Risk::Medium, "deletes files"),
+    ("os.rename", Risk::Medium, "moves files"),
+    ("loadstring", Risk::Low, "runs code built at runtime"),
+    ("os.getenv", Risk::Low, "reads environment variables"),
+];
+
+/// Use of a dangerous call in a mod's scripts.
+#[derive(Debug, Clone, Serialize)]
+pub struct Finding {
+    /// Script the call is in, relative to the mod's directory.
+    pub path: PathBuf,
+    /// Line of the call, starting at 1.
+    pub line: usize,
+    pub call: &'static str,
+    pub risk: Risk,
+    pub description: &'static str,
+}
+
+impl fmt::Display for Finding {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{}:{}: {} {}",
+            self.path.display(),
+            self.line,
+            self.call,
+            self.description
+        )
+    }
+}
+
+/// Dangerous calls found in the scripts of a mod.
+#[derive(Debug, Clone, Serialize)]
+pub struct ModAudit {
+    pub id: ModId,
+    pub path: PathBuf,
+    pub request_no_api_restrictions: bool,
+    pub findings: Vec<Finding>,
+}
+
+impl ModAudit {
+    /// Highest risk among the findings, `None` if nothing was found.
+    pub fn risk(&self) -> Option<Risk> {
+        self.findings.iter().map(|it| it.risk).max()
+    }
+    /// Scans the `.lua` files of an installed mod.
+    pub fn scan(info: &ModInfo) -> Result<Self> {
+        let mut findings = vec![];
+        for entry in WalkDir::new(&info.path)
+            .follow_links(true)
+            .sort_by_file_name()
+        {
+            let entry = entry?;
+            if !entry.file_type().is_file()
+                || entry.path().extension().and_then(|it| it.to_str()) != Some("lua")
+            {
+                continue;
+            }
+
+            let relative = entry
+                .path()
+                .strip_prefix(&info.path)
+                .unwrap_or(entry.path())
+                .to_owned();
+            let content = fs::read(entry.path())?;
+            findings.extend(scan_script(&String::from_utf8_lossy(&content)).map(
+                |(line, (call, risk, description))| Finding {
+                    path: relative.clone(),
+                    line,
+                    call,
+                    risk,
+                    description,
+                },
+            ));
+        }
+
+        Ok(Self {
+            id: info.id.clone(),
+            path: info.path.clone(),
+            request_no_api_restrictions: info.request_no_api_restrictions,
+            findings,
+        })
+    }
+}
+
+/// Audits the installed mods of `mod_list`, in load order.
+pub fn audit_mods(mod_list: &Mods, catalog: &ModCatalog) -> Result<Vec<ModAudit>> {
+    mod_list
+        .mods
+        .iter()
+        .filter_map(|it| catalog.get_mod(it))
+        .map(ModAudit::scan)
+        .collect()
+}
+
+/// Audits every installed mod, sorted by their ids.
+pub fn audit_catalog(catalog: &ModCatalog) -> Result<Vec<ModAudit>> {
+    catalog
+        .values()
+        .sorted_by(|a, b| a.id.cmp(&b.id))
+        .map(ModAudit::scan)
+        .collect()
+}
+
+fn scan_script(
+    script: &str,
+) -> impl Iterator<Item = (usize, (&'static str, Risk, &'static str))> + '_ {
+    script.lines().enumerate().flat_map(|(i, line)| {
+        // Good enough for comments, a `--` inside a string only makes us miss a call
+        let code = line.split_once("--").map_or(line, |(code, _)| code);
+        DANGEROUS_CALLS
+            .into_iter()
+            .filter(move |(call, ..)| contains_call(code, call))
+            .map(move |it| (i + 1, it))
+    })
+}
+
+/// Whether `call` shows up in `code` as a whole identifier, so that e.g. `my_io.open` isn't a match.
+fn contains_call(code: &str, call: &str) -> bool {
+    let is_ident = |c: char| c.is_alphanumeric() || c == '_';
+    code.match_indices(call).any(|(i, _)| {
+        let before = code[..i].chars().next_back();
+        let after = code[i + call.len()..].chars().next();
+        !before.is_some_and(|it| is_ident(it) || it == '.') && !after.is_some_and(is_ident)
+    })
+}
@@ -0,0 +1,196 @@
+use std::{
+    borrow::Cow,
+    collections::HashMap,
+    path::{Component, Path, PathBuf},
+};
+
+use color_eyre::eyre::{bail, Result};
+use fs_err as fs;
+use quick_xml::events::{attributes::Attribute, Event};
+use tracing::warn;
+use walkdir::WalkDir;
+
+use crate::impl_deref_for_newtype;
+
+use super::{
+    mod_config::{Mod, ModId, Mods},
+    NoitaPath,
+};
+
+/// Metadata of an installed mod, parsed from its `mod.xml`.
+#[derive(Debug, Clone, Default)]
+pub struct ModInfo {
+    pub id: ModId,
+    /// Directory the mod is installed in.
+    pub path: PathBuf,
+    /// Display name, falls back to the mod's folder name if `mod.xml` doesn't have one.
+    pub title: String,
+    pub description: String,
+    /// Name shown in the new game menu for game mode mods.
+    pub ui_name: Option<String>,
+    pub request_no_api_restrictions: bool,
+    pub is_game_mode: bool,
+    pub is_translation: bool,
+}
+
+impl ModInfo {
+    pub fn from_dir(path: impl AsRef<Path>, id: ModId) -> Result<Self> {
+        let path = path.as_ref();
+        // mod.xml files are hand written, so they aren't always valid UTF-8
+        let content = fs::read(path.join("mod.xml"))?;
+        let content = String::from_utf8_lossy(&content);
+
+        let mut reader = quick_xml::Reader::from_str(content.trim_start_matches('\u{feff}'));
+        loop {
+            match reader.read_event()? {
+                Event::Start(tag) | Event::Empty(tag) if tag.local_name().as_ref() == b"Mod" => {
+                    let mut info = Self {
+                        title: id.name.clone(),
+                        id,
+                        path: path.to_owned(),
+                        ..Default::default()
+                    };
+
+                    // Noita's own parser is lenient about duplicate attributes, so we are too
+                    for attr in tag.attributes().with_checks(false).flatten() {
+                        let value = attr_value(&attr);
+                        match attr.key.as_ref() {
+                            b"name" if !value.trim().is_empty() => {
+                                info.title = value.trim().to_owned()
+                            }
+                            b"description" => info.description = value.trim().to_owned(),
+                            b"ui_newgame_name" if !value.trim().is_empty() => {
+                                info.ui_name = Some(value.trim().to_owned())
+                            }
+                            b"request_no_api_restrictions" => {
+                                info.request_no_api_restrictions = parse_bool(&value)
+                            }
+                            b"is_game_mode" => info.is_game_mode = parse_bool(&value),
+                            b"is_translation" => info.is_translation = parse_bool(&value),
+                            _ => {}
+                        }
+                    }
+
+                    return Ok(info);
+                }
+                Event::Eof => bail!("No <Mod> element found in {:?}", path.join("mod.xml")),
+                _ => {}
+            }
+        }
+    }
+}
+
+/// Whether `name` can be a mod's folder name, i.e. it names a single entry directly inside the mods directory.
+pub fn is_mod_dir_name(name: &str) -> bool {
+    matches!(
+        Path::new(name).components().collect::<Vec<_>>().as_slice(),
+        [Component::Normal(_)]
+    )
+}
+
+fn attr_value<'a>(attr: &'a Attribute) -> Cow<'a, str> {
+    attr.unescape_value()
+        .unwrap_or_else(|_| String::from_utf8_lossy(&attr.value))
+}
+
+fn parse_bool(value: &str) -> bool {
+    matches!(value.trim(), "1" | "true" | "True" | "TRUE")
+}
+
+/// All installed mods, keyed by the same identity used for entries in [`super::mod_config::Mods`].
+#[derive(Debug, Clone, Default)]
+pub struct ModCatalog(pub HashMap<ModId, ModInfo>);
+
+impl_deref_for_newtype!(ModCatalog, HashMap<ModId, ModInfo>);
+
+impl ModCatalog {
+    /// Scans both the local mods folder and the workshop folder, whichever of them can be found.
+    pub fn scan(noita_path: &NoitaPath) -> Self {
+        Self::from_dirs(noita_path.local_mods(), noita_path.workshop())
+    }
+    pub fn from_dirs(local_mods: Option<PathBuf>, workshop: Option<PathBuf>) -> Self {
+        let mut catalog = Self::default();
+
+        if let Some(dir) = local_mods {
+            for path in subdirs(&dir) {
+                let Some(name) = path.file_name().map(|it| it.to_string_lossy().into_owned())
+                else {
+                    continue;
+                };
+                catalog.insert_from_dir(
+                    path,
+                    ModId {
+                        name,
+                        workshop_item_id: 0,
+                    },
+                );
+            }
+        }
+
+        if let Some(dir) = workshop {
+            for path in subdirs(&dir) {
+                let Some(workshop_item_id) = path
+                    .file_name()
+                    .and_then(|it| it.to_str())
+                    .and_then(|it| it.parse::<usize>().ok())
+                else {
+                    continue;
+                };
+                // Workshop items are stored under their id, the mod's actual name is kept in `mod_id.txt`
+                let name = match fs::read_to_string(path.join("mod_id.txt")) {
+                    Ok(it) => it.trim().to_owned(),
+                    Err(_) => workshop_item_id.to_string(),
+                };
+                catalog.insert_from_dir(
+                    path,
+                    ModId {
+                        name,
+                        workshop_item_id,
+                    },
+                );
+            }
+        }
+
+        catalog
+    }
+    pub fn get_mod(&self, mod_: &Mod) -> Option<&ModInfo> {
+        self.get(&mod_.id())
+    }
+    /// Display name for a mod entry, falling back to its name if it isn't installed.
+    pub fn title<'a>(&'a self, mod_: &'a Mod) -> &'a str {
+        self.get_mod(mod_)
+            .map(|it| it.title.as_str())
+            .unwrap_or(mod_.name.as_str())
+    }
+    /// Mods of `mod_list` that request unrestricted API access, which lets their Lua code use
+    /// `io`, `os` and the like, i.e. do anything on the user's computer.
+    pub fn unrestricted_mods<'a>(&self, mod_list: &'a Mods) -> Vec<&'a Mod> {
+        mod_list
+            .mods
+            .iter()
+            .filter(|it| {
+                self.get_mod(it)
+                    .is_some_and(|it| it.request_no_api_restrictions)
+            })
+            .collect()
+    }
+    fn insert_from_dir(&mut self, path: PathBuf, id: ModId) {
+        match ModInfo::from_dir(&path, id.clone()) {
+            Ok(info) => {
+                self.insert(id, info);
+            }
+            Err(err) => warn!(?path, %err, "Skipping mod"),
+        }
+    }
+}
+
+fn subdirs(dir: &Path) -> impl Iterator<Item = PathBuf> {
+    WalkDir::new(dir)
+        .follow_links(true)
+        .max_depth(1)
+        .min_depth(1)
+        .into_iter()
+        .filter_entry(|e| e.file_type().is_dir())
+        .flat_map(|it| it.ok())
+        .map(|it| it.into_path())
+}
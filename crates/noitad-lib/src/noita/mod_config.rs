@@ -1,45 +1,381 @@
-use std::{io::Write, path::Path};
+pub mod backups;
+pub mod diff;
 
-use color_eyre::eyre::{self, Result};
-use fs_err as fs;
-use serde::{Deserialize, Serialize, Serializer};
+use std::{fmt, path::Path};
 
-fn serialize_bool_as_number<S: Serializer>(value: &bool, serializer: S) -> Result<S::Ok, S::Error> {
-    serializer.serialize_str(if *value { "1" } else { "0" })
-}
+use color_eyre::eyre::{bail, ContextCompat, Result, WrapErr};
+use diff::ModsDiff;
+use fs_err as fs;
+use quick_xml::{escape::escape, events::Event, name::QName};
+use serde::{Deserialize, Serialize};
 
-#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+/// Mod list in the format of Noita's `mod_config.xml`.
+///
+/// Parsing keeps everything the game wrote that isn't modeled here (XML declaration, formatting,
+/// unknown attributes and elements), so that writing the list back is lossless.
+#[derive(Debug, Clone, Default)]
 pub struct Mods {
-    #[serde(rename = "Mod")]
     pub mods: Vec<Mod>,
+    raw: Option<RawMods>,
 }
 
-#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[derive(Debug, Clone, Default)]
 pub struct Mod {
-    #[serde(rename = "@enabled")]
-    #[serde(serialize_with = "serialize_bool_as_number")]
     pub enabled: bool,
-    #[serde(rename = "@name")]
     pub name: String,
-    #[serde(rename = "@settings_fold_open")]
-    #[serde(serialize_with = "serialize_bool_as_number")]
     pub settings_fold_open: bool,
-    #[serde(rename = "@workshop_item_id")]
     pub workshop_item_id: usize,
+    raw: Option<RawMod>,
+}
+
+/// Source text surrounding the `<Mod>` entries.
+#[derive(Debug, Clone, Default)]
+struct RawMods {
+    /// Everything before the root element, e.g. the XML declaration.
+    head: String,
+    /// Root start tag, as written.
+    start_tag: String,
+    /// Content between the last entry and the root end tag.
+    trailing: String,
+    /// Root end tag and everything after it.
+    tail: String,
+}
+
+/// Source text of a `<Mod>` entry.
+#[derive(Debug, Clone, Default)]
+struct RawMod {
+    /// Whitespace, comments or unknown elements preceding the entry.
+    leading: String,
+    attrs: Vec<RawAttr>,
+    /// Whitespace before the end of the start tag.
+    tag_tail: String,
+    /// Inner content of the element, `None` if it was self-closing.
+    body: Option<String>,
+    /// Values of the known attributes at the time of parsing, to tell if they were modified.
+    parsed: (bool, String, bool, usize),
+}
+
+#[derive(Debug, Clone, Default)]
+struct RawAttr {
+    /// Whitespace preceding the attribute.
+    space: String,
+    key: String,
+    /// Everything between the key and the value, usually just `=`.
+    separator: String,
+    quote: char,
+    /// Value as written, still escaped.
+    value: String,
+}
+
+const KNOWN_ATTRS: [&str; 4] = ["enabled", "name", "settings_fold_open", "workshop_item_id"];
+
+/// Identity of a mod entry, the game tells mods apart by their name and workshop id.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
+pub struct ModId {
+    pub name: String,
+    pub workshop_item_id: usize,
+}
+
+impl fmt::Display for ModId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.workshop_item_id {
+            0 => f.write_str(&self.name),
+            id => write!(f, "{} ({})", self.name, id),
+        }
+    }
 }
 
 impl Mod {
+    pub fn new(name: impl Into<String>, workshop_item_id: usize) -> Self {
+        Self {
+            name: name.into(),
+            workshop_item_id,
+            ..Default::default()
+        }
+    }
     pub fn is_local(&self) -> bool {
         self.workshop_item_id == 0
     }
+    pub fn id(&self) -> ModId {
+        ModId {
+            name: self.name.clone(),
+            workshop_item_id: self.workshop_item_id,
+        }
+    }
+    fn from_raw_tag(start_tag: &str, body: Option<String>, leading: String) -> Result<Self> {
+        let (attrs, tag_tail) = parse_raw_attrs(start_tag)?;
+        let mut mod_ = Mod::default();
+        for attr in attrs.iter() {
+            let value = quick_xml::escape::unescape(&attr.value)?;
+            match attr.key.as_str() {
+                "enabled" => mod_.enabled = value.trim() == "1",
+                "name" => mod_.name = value.into_owned(),
+                "settings_fold_open" => mod_.settings_fold_open = value.trim() == "1",
+                "workshop_item_id" => {
+                    mod_.workshop_item_id = value
+                        .trim()
+                        .parse()
+                        .wrap_err_with(|| format!("Invalid workshop_item_id '{}'", value))?
+                }
+                _ => {}
+            }
+        }
+        mod_.raw = Some(RawMod {
+            leading,
+            attrs,
+            tag_tail,
+            body,
+            parsed: (
+                mod_.enabled,
+                mod_.name.clone(),
+                mod_.settings_fold_open,
+                mod_.workshop_item_id,
+            ),
+        });
+
+        Ok(mod_)
+    }
+    /// Value of a known attribute, or `None` if it still has the value it was parsed with.
+    fn modified_attr_value(&self, key: &str) -> Option<String> {
+        let parsed = self.raw.as_ref().map(|it| &it.parsed);
+        let bool_to_str = |b: bool| if b { "1" } else { "0" }.to_owned();
+        match key {
+            "enabled" if parsed.map(|it| it.0) != Some(self.enabled) => {
+                Some(bool_to_str(self.enabled))
+            }
+            "name" if parsed.map(|it| &it.1) != Some(&self.name) => {
+                Some(escape(&self.name).into_owned())
+            }
+            "settings_fold_open" if parsed.map(|it| it.2) != Some(self.settings_fold_open) => {
+                Some(bool_to_str(self.settings_fold_open))
+            }
+            "workshop_item_id" if parsed.map(|it| it.3) != Some(self.workshop_item_id) => {
+                Some(self.workshop_item_id.to_string())
+            }
+            _ => None,
+        }
+    }
+    fn write_xml(&self, xml: &mut String) {
+        let Some(raw) = &self.raw else {
+            xml.push_str("\n  <Mod");
+            for key in KNOWN_ATTRS {
+                xml.push_str(&format!(
+                    " {}=\"{}\"",
+                    key,
+                    self.modified_attr_value(key).unwrap_or_default()
+                ));
+            }
+            xml.push_str(" >\n  </Mod>");
+            return;
+        };
+
+        xml.push_str(&raw.leading);
+        xml.push_str("<Mod");
+        for attr in raw.attrs.iter() {
+            xml.push_str(&attr.space);
+            xml.push_str(&attr.key);
+            xml.push_str(&attr.separator);
+            xml.push(attr.quote);
+            match self.modified_attr_value(&attr.key) {
+                Some(value) if attr.quote == '\'' => xml.push_str(&value.replace('\'', "&apos;")),
+                Some(value) => xml.push_str(&value),
+                None => xml.push_str(&attr.value),
+            }
+            xml.push(attr.quote);
+        }
+        // Known attributes missing from the source
+        for key in KNOWN_ATTRS {
+            if !raw.attrs.iter().any(|it| it.key == key) {
+                let value = self.modified_attr_value(key).unwrap_or_else(|| match key {
+                    "name" => escape(&self.name).into_owned(),
+                    "workshop_item_id" => self.workshop_item_id.to_string(),
+                    _ => "0".to_owned(),
+                });
+                xml.push_str(&format!(" {}=\"{}\"", key, value));
+            }
+        }
+        xml.push_str(&raw.tag_tail);
+        match &raw.body {
+            Some(body) => {
+                xml.push('>');
+                xml.push_str(body);
+                xml.push_str("</Mod>");
+            }
+            None => xml.push_str("/>"),
+        }
+    }
+}
+
+/// Splits the attributes out of a start tag like `<Mod enabled="1" >`, keeping the exact source text.
+fn parse_raw_attrs(start_tag: &str) -> Result<(Vec<RawAttr>, String)> {
+    let inner = start_tag
+        .strip_prefix('<')
+        .and_then(|it| it.strip_suffix('>'))
+        .context("Malformed start tag")?;
+    let inner = inner.strip_suffix('/').unwrap_or(inner);
+    let mut rest = inner.trim_start_matches(|c: char| !c.is_whitespace()); // Element name
+
+    let mut attrs = vec![];
+    loop {
+        let key_start = rest.trim_start();
+        let space = &rest[..rest.len() - key_start.len()];
+        if key_start.is_empty() {
+            return Ok((attrs, space.to_owned()));
+        }
+
+        let key_end = key_start
+            .find(|c: char| c == '=' || c.is_whitespace())
+            .unwrap_or(key_start.len());
+        let (key, after_key) = key_start.split_at(key_end);
+
+        let after_eq = after_key.trim_start();
+        let Some(after_eq) = after_eq.strip_prefix('=') else {
+            bail!("Attribute '{}' has no value", key)
+        };
+        let value_start = after_eq.trim_start();
+        let separator = &after_key[..after_key.len() - value_start.len()];
+
+        let quote = value_start
+            .chars()
+            .next()
+            .context("Attribute value missing")?;
+        if quote != '"' && quote != '\'' {
+            bail!("Attribute '{}' has an unquoted value", key)
+        }
+        let value_len = value_start[1..]
+            .find(quote)
+            .context("Attribute value isn't closed")?;
+
+        attrs.push(RawAttr {
+            space: space.to_owned(),
+            key: key.to_owned(),
+            separator: separator.to_owned(),
+            quote,
+            value: value_start[1..1 + value_len].to_owned(),
+        });
+        rest = &value_start[1 + value_len + 1..];
+    }
 }
 
 impl Mods {
     pub fn from_noita(noita_save_dir: impl AsRef<Path>) -> Result<Self> {
-        quick_xml::de::from_str(&fs::read_to_string(
-            noita_save_dir.as_ref().join("mod_config.xml"),
-        )?)
-        .map_err(eyre::Report::msg)
+        Self::from_file(noita_save_dir.as_ref().join("mod_config.xml"))
+    }
+    pub fn from_file(path: impl AsRef<Path>) -> Result<Self> {
+        Self::from_xml(&fs::read_to_string(path.as_ref())?)
+            .wrap_err_with(|| format!("Failed to parse {:?}", path.as_ref()))
+    }
+    pub fn from_xml(xml: &str) -> Result<Self> {
+        let mut reader = quick_xml::Reader::from_str(xml);
+        let mut raw = RawMods::default();
+        let mut mods = vec![];
+
+        // Everything up to the root element
+        loop {
+            let start = reader.buffer_position() as usize;
+            match reader.read_event()? {
+                Event::Start(tag) if tag.name().as_ref() == b"Mods" => {
+                    raw.head = xml[..start].to_owned();
+                    raw.start_tag = xml[start..reader.buffer_position() as usize].to_owned();
+                    break;
+                }
+                Event::Empty(tag) if tag.name().as_ref() == b"Mods" => {
+                    let end = reader.buffer_position() as usize;
+                    raw.head = xml[..start].to_owned();
+                    raw.start_tag = xml[start..end].to_owned();
+                    raw.tail = xml[end..].to_owned();
+                    return Ok(Self {
+                        mods,
+                        raw: Some(raw),
+                    });
+                }
+                Event::Eof => bail!("No <Mods> element found"),
+                _ => {}
+            }
+        }
+
+        let mut leading = String::new();
+        loop {
+            let start = reader.buffer_position() as usize;
+            match reader.read_event()? {
+                Event::Start(tag) if tag.name().as_ref() == b"Mod" => {
+                    let start_tag = &xml[start..reader.buffer_position() as usize];
+                    let span = reader.read_to_end(QName(b"Mod"))?;
+                    let body = xml[span.start as usize..span.end as usize].to_owned();
+                    mods.push(Mod::from_raw_tag(
+                        start_tag,
+                        Some(body),
+                        std::mem::take(&mut leading),
+                    )?);
+                }
+                Event::Empty(tag) if tag.name().as_ref() == b"Mod" => {
+                    let start_tag = &xml[start..reader.buffer_position() as usize];
+                    mods.push(Mod::from_raw_tag(
+                        start_tag,
+                        None,
+                        std::mem::take(&mut leading),
+                    )?);
+                }
+                Event::Start(tag) => {
+                    // Unknown element, kept along with its content
+                    reader.read_to_end(tag.name())?;
+                    leading.push_str(&xml[start..reader.buffer_position() as usize]);
+                }
+                Event::End(_) => {
+                    raw.trailing = leading;
+                    raw.tail = xml[start..].to_owned();
+                    break;
+                }
+                Event::Eof => bail!("Unexpected end of file, <Mods> isn't closed"),
+                _ => leading.push_str(&xml[start..reader.buffer_position() as usize]),
+            }
+        }
+
+        Ok(Self {
+            mods,
+            raw: Some(raw),
+        })
+    }
+    pub fn to_xml(&self) -> String {
+        let mut xml = String::new();
+        match &self.raw {
+            Some(raw) => {
+                xml.push_str(&raw.head);
+                // A self-closing root can't hold any entries
+                match raw.start_tag.strip_suffix("/>") {
+                    Some(start_tag) if !self.mods.is_empty() => {
+                        xml.push_str(start_tag.trim_end());
+                        xml.push('>');
+                    }
+                    _ => xml.push_str(&raw.start_tag),
+                }
+            }
+            None => xml.push_str("<Mods>"),
+        }
+
+        for mod_ in self.mods.iter() {
+            mod_.write_xml(&mut xml);
+        }
+
+        match &self.raw {
+            Some(raw) if raw.start_tag.ends_with("/>") => {
+                if !self.mods.is_empty() {
+                    xml.push_str("\n</Mods>");
+                }
+                xml.push_str(&raw.tail);
+            }
+            Some(raw) => {
+                xml.push_str(&raw.trailing);
+                xml.push_str(&raw.tail);
+            }
+            None => xml.push_str("\n</Mods>\n"),
+        }
+
+        xml
+    }
+    /// Changes that turn this mod list into `other`.
+    pub fn diff(&self, other: &Mods) -> ModsDiff {
+        ModsDiff::new(self, other)
     }
     pub fn sync_with_noita(&mut self, noita_save_dir: impl AsRef<Path>) -> Result<()> {
         let noita_mod_list = Self::from_noita(noita_save_dir.as_ref())?;
@@ -59,8 +395,8 @@ impl Mods {
             }
 
             if let Some(i) = common {
-                self.mods[i].enabled = mod_.enabled;
-                self.mods[i].settings_fold_open = mod_.settings_fold_open;
+                // Takes over the enabled state and anything else the game might have written for it
+                self.mods[i] = mod_.clone();
             } else {
                 new_mods.push(mod_);
             }
@@ -72,10 +408,177 @@ impl Mods {
 
         Ok(())
     }
-    pub fn overwrite_noita_mod_list(&self, noita_save_dir: impl AsRef<Path>) -> Result<()> {
-        fs::File::create(noita_save_dir.as_ref().join("mod_config.xml"))?
-            .write_fmt(format_args!("{}", quick_xml::se::to_string(self)?))?;
+    pub fn position(&self, id: &ModId) -> Option<usize> {
+        self.mods.iter().position(|it| it.id() == *id)
+    }
+    /// Moves the mod at `from` so that it ends up at index `to`, shifting the mods in between.
+    pub fn move_to(&mut self, from: usize, to: usize) -> Result<()> {
+        let len = self.mods.len();
+        if from >= len || to >= len {
+            bail!("Cannot move mod from {from} to {to}, there are only {len} mods")
+        }
+        let mod_ = self.mods.remove(from);
+        self.mods.insert(to, mod_);
 
         Ok(())
     }
+    pub fn move_before(&mut self, id: &ModId, anchor: &ModId) -> Result<()> {
+        let (from, to) = self.anchored_positions(id, anchor)?;
+        self.move_to(from, if from < to { to - 1 } else { to })
+    }
+    pub fn move_after(&mut self, id: &ModId, anchor: &ModId) -> Result<()> {
+        let (from, to) = self.anchored_positions(id, anchor)?;
+        self.move_to(from, if from < to { to } else { to + 1 })
+    }
+    /// Stable sort of the load order, mods comparing equal keep their relative order.
+    pub fn sort_by_key<K: Ord>(&mut self, f: impl FnMut(&Mod) -> K) {
+        self.mods.sort_by_key(f);
+    }
+    fn anchored_positions(&self, id: &ModId, anchor: &ModId) -> Result<(usize, usize)> {
+        if id == anchor {
+            bail!("Cannot move mod '{}' relative to itself", id.name)
+        }
+        let from = self
+            .position(id)
+            .with_context(|| format!("Mod '{}' not found", id.name))?;
+        let to = self
+            .position(anchor)
+            .with_context(|| format!("Mod '{}' not found", anchor.name))?;
+
+        Ok((from, to))
+    }
+    pub fn overwrite_noita_mod_list(&self, noita_save_dir: impl AsRef<Path>) -> Result<()> {
+        backups::write_mod_config(noita_save_dir, self.to_xml())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// As written by the game.
+    const NOITA_MOD_CONFIG: &str = r#"<Mods>
+  <Mod
+    enabled="1"
+    name="nightmare"
+    settings_fold_open="0"
+    workshop_item_id="0" >
+  </Mod>
+  <Mod
+    enabled="0"
+    name="grahamsperks"
+    settings_fold_open="1"
+    workshop_item_id="2234567890" >
+  </Mod>
+  <Mod
+    enabled="1"
+    name="example"
+    settings_fold_open="0"
+    workshop_item_id="0" >
+  </Mod>
+</Mods>
+
+"#;
+
+    fn round_trip(xml: &str) -> Mods {
+        let mods = Mods::from_xml(xml).unwrap();
+        assert_eq!(mods.to_xml(), xml);
+        mods
+    }
+
+    #[test]
+    fn round_trips_game_mod_config() {
+        let mods = round_trip(NOITA_MOD_CONFIG);
+        assert_eq!(
+            mods.mods.iter().map(|it| it.id()).collect::<Vec<_>>(),
+            [
+                ModId {
+                    name: "nightmare".into(),
+                    workshop_item_id: 0
+                },
+                ModId {
+                    name: "grahamsperks".into(),
+                    workshop_item_id: 2234567890
+                },
+                ModId {
+                    name: "example".into(),
+                    workshop_item_id: 0
+                },
+            ]
+        );
+        assert!(mods.mods[0].enabled);
+        assert!(!mods.mods[1].enabled);
+        assert!(mods.mods[1].settings_fold_open);
+    }
+
+    #[test]
+    fn round_trips_xml_declaration_and_comments() {
+        round_trip(concat!(
+            "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\r\n",
+            "<!-- written by hand -->\r\n",
+            "<Mods>\r\n",
+            "  <!-- disabled for now -->\r\n",
+            "  <Mod enabled=\"0\" name=\"a\" settings_fold_open=\"0\" workshop_item_id=\"0\"/>\r\n",
+            "</Mods>\r\n",
+        ));
+    }
+
+    #[test]
+    fn round_trips_unknown_attributes_and_elements() {
+        let mods = round_trip(concat!(
+            "<Mods version=\"2\">\n",
+            "  <Profile name=\"unknown\"><Nested/></Profile>\n",
+            "  <Mod enabled=\"1\" name=\"a\" priority=\"5\" settings_fold_open=\"0\" workshop_item_id=\"0\">\n",
+            "    <Setting key=\"speed\" value=\"2\"/>\n",
+            "  </Mod>\n",
+            "  <Extra>text</Extra>\n",
+            "</Mods>\n",
+        ));
+        assert_eq!(mods.mods.len(), 1);
+        assert_eq!(mods.mods[0].name, "a");
+    }
+
+    #[test]
+    fn round_trips_single_quotes_and_entities() {
+        let mods = round_trip(concat!(
+            "<Mods>\n",
+            "  <Mod enabled='1' name='Tom &amp; Jerry&apos;s &#x41;' settings_fold_open = '0' workshop_item_id='0' >\n",
+            "  </Mod>\n",
+            "</Mods>\n",
+        ));
+        assert_eq!(mods.mods[0].name, "Tom & Jerry's A");
+    }
+
+    #[test]
+    fn round_trips_self_closing_root() {
+        let mods = round_trip("<?xml version=\"1.0\"?>\n<Mods />\n");
+        assert!(mods.mods.is_empty());
+    }
+
+    #[test]
+    fn adds_entries_to_self_closing_root() {
+        let mut mods = Mods::from_xml("<Mods/>\n").unwrap();
+        mods.mods.push(Mod::new("a", 0));
+        let xml = mods.to_xml();
+
+        let parsed = Mods::from_xml(&xml).unwrap();
+        assert_eq!(parsed.mods.len(), 1);
+        assert_eq!(parsed.mods[0].name, "a");
+        assert!(xml.ends_with("</Mods>\n"));
+    }
+
+    #[test]
+    fn only_rewrites_modified_attributes() {
+        let xml = concat!(
+            "<Mods>\n",
+            "  <Mod enabled='1' name='a &amp; b' extra=\"x\" settings_fold_open=\"0\" workshop_item_id=\"0\" />\n",
+            "</Mods>\n",
+        );
+        let mut mods = Mods::from_xml(xml).unwrap();
+        mods.mods[0].enabled = false;
+        assert_eq!(mods.to_xml(), xml.replace("enabled='1'", "enabled='0'"));
+
+        mods.mods[0].name = "it's".into();
+        assert!(mods.to_xml().contains("name='it&apos;s'"));
+    }
 }
@@ -0,0 +1,152 @@
+use std::{
+    fmt,
+    path::{Path, PathBuf},
+};
+
+use chrono::NaiveDateTime;
+use color_eyre::eyre::{ContextCompat, Result};
+use fs_err as fs;
+use itertools::Itertools;
+use tracing::{info, warn};
+use walkdir::WalkDir;
+
+use crate::ext::PathExt;
+
+use super::Mods;
+
+const BACKUPS_DIR: &str = "noitad_mod_config_backups";
+const BACKUP_PREFIX: &str = "mod_config.xml.";
+const TIME_FORMAT: &str = "%Y-%m-%dT%H-%M-%S%.3f";
+/// Number of backups kept around, older ones are removed.
+pub const MAX_BACKUPS: usize = 10;
+
+/// Backups of the game's `mod_config.xml`, kept in a directory next to the save directory.
+#[derive(Debug, Clone)]
+pub struct ModConfigBackups {
+    noita_save_dir: PathBuf,
+    dir: PathBuf,
+}
+
+#[derive(Debug, Clone)]
+pub struct ModConfigBackup {
+    pub path: PathBuf,
+    pub time: NaiveDateTime,
+}
+
+impl ModConfigBackup {
+    /// Name the backup is referred to by, its timestamp.
+    pub fn name(&self) -> &str {
+        self.path
+            .file_name()
+            .and_then(|it| it.to_str())
+            .and_then(|it| it.strip_prefix(BACKUP_PREFIX))
+            .unwrap_or_default()
+    }
+    pub fn mods(&self) -> Result<Mods> {
+        Mods::from_file(&self.path)
+    }
+}
+
+impl fmt::Display for ModConfigBackup {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.time.format("%Y-%m-%d %H:%M:%S"))?;
+        if let Ok(mods) = self.mods() {
+            write!(
+                f,
+                " ({} of {} mods enabled)",
+                mods.mods.iter().filter(|it| it.enabled).count(),
+                mods.mods.len()
+            )?;
+        }
+
+        Ok(())
+    }
+}
+
+impl ModConfigBackups {
+    pub fn new(noita_save_dir: impl AsRef<Path>) -> Self {
+        let noita_save_dir = noita_save_dir.as_ref().to_owned();
+        let dir = noita_save_dir
+            .parent()
+            .unwrap_or(&noita_save_dir)
+            .join(BACKUPS_DIR);
+
+        Self {
+            noita_save_dir,
+            dir,
+        }
+    }
+    /// Copies the current `mod_config.xml`, if there's one, and removes backups over [`MAX_BACKUPS`].
+    pub fn backup(&self) -> Result<Option<ModConfigBackup>> {
+        let mod_config = self.noita_save_dir.join("mod_config.xml");
+        if !mod_config.is_file() {
+            return Ok(None);
+        }
+
+        fs::create_dir_all(&self.dir)?;
+        let time = chrono::Local::now().naive_local();
+        let path = self
+            .dir
+            .join(format!("{BACKUP_PREFIX}{}", time.format(TIME_FORMAT)));
+        fs::copy(&mod_config, &path)?;
+        info!(?path, "Backed up mod_config");
+
+        for old in self.list()?.into_iter().skip(MAX_BACKUPS) {
+            _ = fs::remove_file(&old.path).inspect_err(|e| warn!(%e));
+        }
+
+        Ok(Some(ModConfigBackup { path, time }))
+    }
+    /// Backups sorted from newest to oldest.
+    pub fn list(&self) -> Result<Vec<ModConfigBackup>> {
+        if !self.dir.is_dir() {
+            return Ok(vec![]);
+        }
+
+        Ok(WalkDir::new(&self.dir)
+            .max_depth(1)
+            .min_depth(1)
+            .into_iter()
+            .flat_map(|it| it.ok())
+            .filter(|it| it.file_type().is_file())
+            .filter_map(|it| {
+                let time = it
+                    .file_name()
+                    .to_str()?
+                    .strip_prefix(BACKUP_PREFIX)
+                    .and_then(|it| NaiveDateTime::parse_from_str(it, TIME_FORMAT).ok())?;
+                Some(ModConfigBackup {
+                    path: it.into_path(),
+                    time,
+                })
+            })
+            .sorted_by(|a, b| Ord::cmp(&b.time, &a.time))
+            .collect_vec())
+    }
+    pub fn find(&self, name: impl AsRef<str>) -> Result<ModConfigBackup> {
+        self.list()?
+            .into_iter()
+            .find(|it| it.name() == name.as_ref())
+            .with_context(|| format!("No mod_config backup named '{}'", name.as_ref()))
+    }
+    /// Rolls `mod_config.xml` back to the backup, the state being replaced is backed up as well.
+    pub fn restore(&self, backup: &ModConfigBackup) -> Result<()> {
+        let contents = fs::read_to_string(&backup.path)?;
+        write_mod_config(&self.noita_save_dir, contents)
+    }
+}
+
+/// Replaces the game's `mod_config.xml`, backing up the current one first.
+///
+/// The new file is written out in full before it takes the place of the old one,
+/// so the game never ends up with a partially written mod list.
+pub fn write_mod_config(
+    noita_save_dir: impl AsRef<Path>,
+    contents: impl AsRef<[u8]>,
+) -> Result<()> {
+    ModConfigBackups::new(noita_save_dir.as_ref()).backup()?;
+    noita_save_dir
+        .as_ref()
+        .join("mod_config.xml")
+        .write_atomic(contents)
+}
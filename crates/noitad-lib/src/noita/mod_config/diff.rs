@@ -0,0 +1,194 @@
+use std::collections::{HashMap, HashSet};
+
+use itertools::Itertools;
+use serde::Serialize;
+
+use super::{ModId, Mods};
+
+/// Differences between two mod lists, as the changes that turn the first list into the second one.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct ModsDiff {
+    /// Mods only in the second list.
+    pub added: Vec<ModId>,
+    /// Mods only in the first list.
+    pub removed: Vec<ModId>,
+    /// Mods in both lists that are only enabled in the second one.
+    pub enabled: Vec<ModId>,
+    /// Mods in both lists that are only enabled in the first one.
+    pub disabled: Vec<ModId>,
+    /// Mods in both lists that were moved relative to the other mods in both lists.
+    pub reordered: Vec<ModId>,
+}
+
+impl ModsDiff {
+    pub fn new(from: &Mods, to: &Mods) -> Self {
+        let mut diff = Self::default();
+
+        let mut from_mods = HashMap::new();
+        for (i, mod_) in from.mods.iter().enumerate() {
+            from_mods.entry(mod_.id()).or_insert((i, mod_.enabled));
+        }
+        let to_ids = to.mods.iter().map(|it| it.id()).collect::<Vec<_>>();
+        let mut seen = HashSet::new();
+
+        // Positions in the first list of the mods in both lists, in the order of the second list
+        let mut common = vec![];
+        for (mod_, id) in to.mods.iter().zip(to_ids.iter()) {
+            // The game only goes by the first entry of a mod
+            if !seen.insert(id) {
+                continue;
+            }
+            match from_mods.get(id) {
+                Some(&(i, enabled)) => {
+                    match (enabled, mod_.enabled) {
+                        (false, true) => diff.enabled.push(id.clone()),
+                        (true, false) => diff.disabled.push(id.clone()),
+                        _ => {}
+                    }
+                    common.push((id, i));
+                }
+                None => diff.added.push(id.clone()),
+            }
+        }
+
+        diff.removed = from
+            .mods
+            .iter()
+            .map(|it| it.id())
+            .filter(|it| !seen.contains(it))
+            .unique()
+            .collect();
+
+        // Whatever isn't part of the longest run kept in the same relative order must've been moved
+        let kept =
+            longest_increasing_subsequence(&common.iter().map(|it| it.1).collect::<Vec<_>>());
+        diff.reordered = common
+            .iter()
+            .enumerate()
+            .filter(|(i, _)| !kept.contains(i))
+            .map(|(_, (id, _))| (*id).clone())
+            .collect();
+
+        diff
+    }
+    pub fn is_empty(&self) -> bool {
+        self.added.is_empty()
+            && self.removed.is_empty()
+            && self.enabled.is_empty()
+            && self.disabled.is_empty()
+            && self.reordered.is_empty()
+    }
+}
+
+/// Returns the indices of the items that make up the longest strictly increasing subsequence.
+fn longest_increasing_subsequence(items: &[usize]) -> Vec<usize> {
+    // Index of the smallest tail item of every subsequence length found so far
+    let mut tails: Vec<usize> = vec![];
+    let mut prev = vec![None; items.len()];
+
+    for (i, item) in items.iter().enumerate() {
+        let len = tails.partition_point(|&it| items[it] < *item);
+        if len > 0 {
+            prev[i] = Some(tails[len - 1]);
+        }
+        if len == tails.len() {
+            tails.push(i);
+        } else {
+            tails[len] = i;
+        }
+    }
+
+    let mut indices = vec![];
+    let mut next = tails.last().copied();
+    while let Some(i) = next {
+        indices.push(i);
+        next = prev[i];
+    }
+    indices.reverse();
+
+    indices
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::noita::mod_config::Mod;
+
+    /// Mod list of local mods, a name with a `+` after it being enabled.
+    fn mods(list: &[&str]) -> Mods {
+        Mods {
+            mods: list
+                .iter()
+                .map(|it| {
+                    let mut mod_ = Mod::new(it.trim_end_matches('+'), 0);
+                    mod_.enabled = it.ends_with('+');
+                    mod_
+                })
+                .collect(),
+            ..Default::default()
+        }
+    }
+
+    fn ids(names: &[&str]) -> Vec<ModId> {
+        names.iter().map(|it| Mod::new(*it, 0).id()).collect()
+    }
+
+    #[test]
+    fn same_list_has_no_changes() {
+        let a = mods(&["a+", "b", "c+"]);
+        assert!(ModsDiff::new(&a, &a).is_empty());
+    }
+
+    #[test]
+    fn finds_added_removed_and_toggled_mods() {
+        let diff = ModsDiff::new(&mods(&["a+", "b", "c+"]), &mods(&["a", "b+", "d"]));
+        assert_eq!(diff.added, ids(&["d"]));
+        assert_eq!(diff.removed, ids(&["c"]));
+        assert_eq!(diff.enabled, ids(&["b"]));
+        assert_eq!(diff.disabled, ids(&["a"]));
+        assert!(diff.reordered.is_empty());
+    }
+
+    #[test]
+    fn only_the_moved_mod_is_reordered() {
+        let diff = ModsDiff::new(&mods(&["a", "b", "c", "d"]), &mods(&["b", "c", "d", "a"]));
+        assert_eq!(diff.reordered, ids(&["a"]));
+
+        let diff = ModsDiff::new(&mods(&["a", "b", "c", "d"]), &mods(&["a", "d", "b", "c"]));
+        assert_eq!(diff.reordered, ids(&["d"]));
+    }
+
+    #[test]
+    fn added_and_removed_mods_dont_count_as_reordered() {
+        let diff = ModsDiff::new(&mods(&["x", "a", "b"]), &mods(&["a", "y", "b"]));
+        assert_eq!(diff.added, ids(&["y"]));
+        assert_eq!(diff.removed, ids(&["x"]));
+        assert!(diff.reordered.is_empty());
+    }
+
+    #[test]
+    fn only_first_entry_of_a_duplicate_counts() {
+        let diff = ModsDiff::new(&mods(&["a", "b"]), &mods(&["a+", "b", "a"]));
+        assert_eq!(diff.enabled, ids(&["a"]));
+        assert!(diff.added.is_empty());
+        assert!(diff.reordered.is_empty());
+    }
+
+    #[test]
+    fn workshop_and_local_mods_of_the_same_name_differ() {
+        let from = mods(&["a"]);
+        let mut to = mods(&[]);
+        to.mods.push(Mod::new("a", 1234));
+        let diff = ModsDiff::new(&from, &to);
+        assert_eq!(diff.added, [Mod::new("a", 1234).id()]);
+        assert_eq!(diff.removed, ids(&["a"]));
+    }
+
+    #[test]
+    fn longest_increasing_subsequence_indices() {
+        assert_eq!(longest_increasing_subsequence(&[]), Vec::<usize>::new());
+        assert_eq!(longest_increasing_subsequence(&[0, 1, 2]), [0, 1, 2]);
+        assert_eq!(longest_increasing_subsequence(&[3, 0, 1, 2]), [1, 2, 3]);
+        assert_eq!(longest_increasing_subsequence(&[2, 0, 3, 1, 4]).len(), 3);
+    }
+}
@@ -0,0 +1,108 @@
+use std::{io, path::Path};
+
+use color_eyre::eyre::Result;
+use fs_err as fs;
+use serde::{Deserialize, Serialize};
+use walkdir::WalkDir;
+
+use super::{
+    mod_config::{ModId, Mods},
+    orphans::InstalledMods,
+};
+
+/// Hash of an installed mod's files, at the time its profile was saved.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ModHash {
+    #[serde(flatten)]
+    pub id: ModId,
+    pub hash: String,
+}
+
+/// Hashes the paths and contents of all the files under `dir`.
+pub fn hash_dir(dir: impl AsRef<Path>) -> Result<String> {
+    let dir = dir.as_ref();
+    let mut hasher = blake3::Hasher::new();
+    for entry in WalkDir::new(dir)
+        .follow_links(true)
+        .min_depth(1)
+        .sort_by_file_name()
+    {
+        let entry = entry?;
+        if !entry.file_type().is_file() {
+            continue;
+        }
+        // Paths are hashed as well, so that renaming or moving a file counts as a change
+        let relative = entry.path().strip_prefix(dir)?.to_string_lossy();
+        hasher.update(relative.replace(std::path::MAIN_SEPARATOR, "/").as_bytes());
+        hasher.update(&[0]);
+        hasher.update(&entry.metadata()?.len().to_le_bytes());
+        io::copy(&mut fs::File::open(entry.path())?, &mut hasher)?;
+    }
+
+    Ok(hasher.finalize().to_hex().to_string())
+}
+
+/// Hashes every installed mod of `mod_list`, mods that can't be found are left out.
+pub fn hash_mods(mod_list: &Mods, installed: &InstalledMods) -> Result<Vec<ModHash>> {
+    mod_list
+        .mods
+        .iter()
+        .filter_map(|it| Some((it.id(), installed.mod_dir(it).filter(|it| it.is_dir())?)))
+        .map(|(id, dir)| {
+            Ok(ModHash {
+                id,
+                hash: hash_dir(dir)?,
+            })
+        })
+        .collect()
+}
+
+/// Brings `recorded` in line with `mod_list` without touching the hashes it already has.
+///
+/// Mods no longer in the list are dropped and newly added ones are hashed, so that a change to an
+/// already recorded mod keeps showing up until it's accepted.
+pub fn update_hashes(
+    recorded: &[ModHash],
+    mod_list: &Mods,
+    installed: &InstalledMods,
+) -> Result<Vec<ModHash>> {
+    let mut hashes = vec![];
+    for mod_ in mod_list.mods.iter() {
+        let id = mod_.id();
+        if let Some(recorded) = recorded.iter().find(|it| it.id == id) {
+            hashes.push(recorded.clone());
+        } else if let Some(dir) = installed.mod_dir(mod_).filter(|it| it.is_dir()) {
+            hashes.push(ModHash {
+                id,
+                hash: hash_dir(dir)?,
+            });
+        }
+    }
+
+    Ok(hashes)
+}
+
+/// Mods of `mod_list` whose files differ from when `recorded` was hashed.
+///
+/// Mods without a recorded hash, or that aren't installed anymore, aren't included.
+pub fn changed_mods(
+    recorded: &[ModHash],
+    mod_list: &Mods,
+    installed: &InstalledMods,
+) -> Result<Vec<ModId>> {
+    let mut changed = vec![];
+    for mod_ in mod_list.mods.iter() {
+        let id = mod_.id();
+        let Some(recorded) = recorded.iter().find(|it| it.id == id) else {
+            continue;
+        };
+        let Some(dir) = installed.mod_dir(mod_).filter(|it| it.is_dir()) else {
+            continue;
+        };
+        if hash_dir(dir)? != recorded.hash {
+            changed.push(id);
+        }
+    }
+
+    Ok(changed)
+}
@@ -0,0 +1,150 @@
+use std::path::{Path, PathBuf};
+
+use color_eyre::eyre::{bail, Context, ContextCompat, Result};
+use flate2::read::GzDecoder;
+use fs_err as fs;
+use itertools::Itertools;
+use tracing::{info, warn};
+use walkdir::WalkDir;
+
+use super::{mod_catalog::ModInfo, mod_config::ModId};
+
+const STAGING_DIR: &str = ".noitad_install";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ArchiveKind {
+    Zip,
+    Tar,
+    TarGz,
+}
+
+impl ArchiveKind {
+    /// Tells the kind of archive from its file extension.
+    pub fn from_path(path: impl AsRef<Path>) -> Option<Self> {
+        let name = path.as_ref().file_name()?.to_string_lossy().to_lowercase();
+        if name.ends_with(".zip") {
+            Some(Self::Zip)
+        } else if name.ends_with(".tar.gz") || name.ends_with(".tgz") {
+            Some(Self::TarGz)
+        } else if name.ends_with(".tar") {
+            Some(Self::Tar)
+        } else {
+            None
+        }
+    }
+    /// File name of the archive without its extension.
+    fn stem(self, path: &Path) -> Option<String> {
+        let name = path.file_name()?.to_string_lossy().into_owned();
+        let len = match self {
+            ArchiveKind::Zip => ".zip".len(),
+            ArchiveKind::Tar => ".tar".len(),
+            ArchiveKind::TarGz if name.to_lowercase().ends_with(".tgz") => ".tgz".len(),
+            ArchiveKind::TarGz => ".tar.gz".len(),
+        };
+        Some(name[..name.len() - len].to_owned()).filter(|it| !it.is_empty())
+    }
+}
+
+/// Installs the mod packed in `archive` into the `local_mods` folder.
+///
+/// The mod is the shallowest folder of the archive with a `mod.xml` in it, its name is taken from that
+/// folder, or from the archive's name if `mod.xml` is at the root of the archive.
+/// An already installed mod with the same name is only replaced if `force` is set.
+pub fn install_archive(
+    archive: impl AsRef<Path>,
+    local_mods: impl AsRef<Path>,
+    force: bool,
+) -> Result<ModInfo> {
+    let archive = archive.as_ref();
+    let local_mods = local_mods.as_ref();
+    let kind = ArchiveKind::from_path(archive).with_context(|| {
+        format!(
+            "Unsupported archive {:?}, expected a .zip, .tar or .tar.gz file",
+            archive
+        )
+    })?;
+
+    // Unpacked inside the mods folder, so that moving the mod in place is just a rename
+    let staging = local_mods.join(STAGING_DIR);
+    if staging.exists() {
+        fs::remove_dir_all(&staging)?;
+    }
+    fs::create_dir_all(&staging)?;
+
+    let res = (|| {
+        unpack(kind, archive, &staging)
+            .with_context(|| format!("Couldn't unpack {:?}", archive))?;
+        let mod_dir = find_mod_dir(&staging)?;
+        let name = match mod_dir == staging {
+            true => kind.stem(archive),
+            false => mod_dir
+                .file_name()
+                .map(|it| it.to_string_lossy().into_owned()),
+        }
+        .context("Couldn't tell the name of the mod")?;
+
+        let info = ModInfo::from_dir(
+            &mod_dir,
+            ModId {
+                name: name.clone(),
+                workshop_item_id: 0,
+            },
+        )
+        .context("Archive doesn't contain a valid mod")?;
+
+        let target = local_mods.join(&name);
+        if target.exists() {
+            if !force {
+                bail!("Mod '{}' is already installed at {:?}", name, target)
+            }
+            fs::remove_dir_all(&target)?;
+        }
+        fs::rename(&mod_dir, &target)?;
+        info!(name, ?archive, "Installed mod");
+
+        Ok(ModInfo {
+            path: target,
+            ..info
+        })
+    })();
+    if staging.exists() {
+        _ = fs::remove_dir_all(&staging).inspect_err(|e| warn!(%e));
+    }
+
+    res
+}
+
+fn unpack(kind: ArchiveKind, archive: &Path, dest: &Path) -> Result<()> {
+    let file = fs::File::open(archive)?;
+    // Both skip over entries pointing outside of `dest`
+    match kind {
+        ArchiveKind::Zip => zip::ZipArchive::new(file)?.extract(dest)?,
+        ArchiveKind::Tar => tar::Archive::new(file).unpack(dest)?,
+        ArchiveKind::TarGz => tar::Archive::new(GzDecoder::new(file)).unpack(dest)?,
+    }
+
+    Ok(())
+}
+
+fn find_mod_dir(root: &Path) -> Result<PathBuf> {
+    let candidates = WalkDir::new(root)
+        .sort_by_file_name()
+        .into_iter()
+        .flatten()
+        .filter(|it| it.file_type().is_file() && it.file_name() == "mod.xml")
+        .map(|it| (it.depth(), it.into_path()))
+        .min_set_by_key(|(depth, _)| *depth);
+
+    match candidates.as_slice() {
+        [] => bail!("Archive doesn't contain a mod.xml"),
+        [(_, path)] => Ok(path.parent().unwrap_or(root).to_owned()),
+        _ => bail!(
+            "Archive contains more than one mod: {}",
+            candidates
+                .iter()
+                .flat_map(|(_, it)| it.parent()?.file_name())
+                .map(|it| it.to_string_lossy())
+                .join(", ")
+        ),
+    }
+}
@@ -0,0 +1,130 @@
+use std::path::Path;
+
+use color_eyre::eyre::{bail, Result};
+use fs_err as fs;
+use quick_xml::escape::escape;
+use tracing::{info, warn};
+
+use super::{mod_catalog::ModInfo, mod_config::ModId};
+
+const MOD_XML: &str = r#"<Mod
+	name="{title}"
+	description="{description}"
+	request_no_api_restrictions="0"
+>
+</Mod>
+"#;
+
+const INIT_LUA: &str = r#"dofile_once("data/scripts/lib/utilities.lua")
+
+-- Called once all mods are loaded, before the world is created
+function OnModInit()
+end
+
+-- Called when the player is spawned, which also happens when a save is loaded
+function OnPlayerSpawned(player_entity)
+	GamePrint("{title} is running")
+end
+"#;
+
+const SETTINGS_LUA: &str = r#"dofile("data/scripts/lib/mod_settings.lua")
+
+local mod_id = "{name}"
+mod_settings_version = 1
+mod_settings = {
+	{
+		id = "example",
+		ui_name = "Example",
+		ui_description = "Read it with ModSettingsGet(\"{name}.example\")",
+		value_default = false,
+		scope = MOD_SETTING_SCOPE_RUNTIME,
+	},
+}
+
+function ModSettingsUpdate(init_scope)
+	mod_settings_update(mod_id, mod_settings, init_scope)
+end
+
+function ModSettingsGuiCount()
+	return mod_settings_gui_count(mod_id, mod_settings)
+end
+
+function ModSettingsGui(gui, in_main_menu)
+	mod_settings_gui(mod_id, mod_settings, gui, in_main_menu)
+end
+"#;
+
+/// What goes into a new mod's `mod.xml`.
+#[derive(Debug, Clone, Default)]
+pub struct ModTemplate {
+    /// Folder name of the mod, which is also its id in the game's mod list.
+    pub name: String,
+    /// Display name, the folder name is used if it's empty.
+    pub title: String,
+    pub description: String,
+    /// Include a `settings.lua` with an example setting.
+    pub settings: bool,
+}
+
+impl ModTemplate {
+    /// Creates the mod's skeleton in `local_mods`.
+    pub fn create(&self, local_mods: impl AsRef<Path>) -> Result<ModInfo> {
+        let name = self.name.as_str();
+        if name.is_empty()
+            || !name
+                .chars()
+                .all(|it| it.is_ascii_alphanumeric() || matches!(it, '_' | '-'))
+        {
+            bail!(
+                "Invalid mod name '{}', only letters, digits, '_' and '-' are allowed",
+                name
+            )
+        }
+        let title = match self.title.trim() {
+            "" => name,
+            title => title,
+        };
+
+        let dir = local_mods.as_ref().join(name);
+        if dir.exists() {
+            bail!("A mod named '{}' already exists at {:?}", name, dir)
+        }
+        fs::create_dir_all(&dir)?;
+
+        let fill = |template: &str, escape_value: fn(&str) -> String| {
+            template
+                .replace("{name}", name)
+                .replace("{title}", &escape_value(title))
+                .replace("{description}", &escape_value(&self.description))
+        };
+        let res = (|| -> Result<()> {
+            fs::write(dir.join("mod.xml"), fill(MOD_XML, |it| escape(it).into()))?;
+            fs::write(dir.join("init.lua"), fill(INIT_LUA, escape_lua))?;
+            if self.settings {
+                fs::write(dir.join("settings.lua"), fill(SETTINGS_LUA, escape_lua))?;
+            }
+            Ok(())
+        })();
+        if let Err(err) = res {
+            _ = fs::remove_dir_all(&dir).inspect_err(|e| warn!(%e));
+            return Err(err);
+        }
+        info!(?dir, "Created mod");
+
+        ModInfo::from_dir(
+            &dir,
+            ModId {
+                name: name.to_owned(),
+                workshop_item_id: 0,
+            },
+        )
+    }
+}
+
+/// Escapes text for use inside a double quoted Lua string.
+fn escape_lua(value: &str) -> String {
+    value
+        .replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace('\n', "\\n")
+}
@@ -0,0 +1,222 @@
+use std::{
+    fmt,
+    path::{Path, PathBuf},
+};
+
+use chrono::NaiveDateTime;
+use color_eyre::eyre::{self, bail, ContextCompat, Result};
+use fs_err as fs;
+use itertools::Itertools;
+use serde::{Deserialize, Serialize};
+use tracing::{info, warn};
+use walkdir::WalkDir;
+
+use crate::{defines::MOD_TRASH_DIR, ext::PathExt};
+
+use super::mod_catalog::is_mod_dir_name;
+
+const RECORD_NAME: &str = "trash.toml";
+const MOD_DIR: &str = "mod";
+const TIME_FORMAT: &str = "%Y-%m-%dT%H-%M-%S%.3f";
+
+/// Where an uninstalled mod was in a profile's mod list, so that it can be put back there.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ModPlacement {
+    pub profile: String,
+    pub position: usize,
+    pub enabled: bool,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct TrashRecord {
+    /// Folder name of the mod under `mods/`.
+    pub name: String,
+    pub title: String,
+    /// Profiles the mod was removed from.
+    pub profiles: Vec<ModPlacement>,
+}
+
+#[derive(Debug, Clone)]
+pub struct TrashedMod {
+    /// Name the trashed mod is referred to by, its timestamp.
+    pub id: String,
+    pub path: PathBuf,
+    pub time: NaiveDateTime,
+    pub record: TrashRecord,
+}
+
+impl TrashedMod {
+    /// The mod's files, as they were under `mods/`.
+    pub fn mod_dir(&self) -> PathBuf {
+        self.path.join(MOD_DIR)
+    }
+}
+
+impl fmt::Display for TrashedMod {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} {}",
+            self.time.format("%Y-%m-%d %H:%M:%S"),
+            self.record.name
+        )?;
+        if self.record.title != self.record.name {
+            write!(f, " ({})", self.record.title)?;
+        }
+        Ok(())
+    }
+}
+
+/// Uninstalled local mods, kept under [`MOD_TRASH_DIR`] until the trash is emptied.
+#[derive(Debug, Clone)]
+pub struct ModTrash {
+    dir: PathBuf,
+}
+
+impl Default for ModTrash {
+    fn default() -> Self {
+        Self::new(MOD_TRASH_DIR.as_path())
+    }
+}
+
+impl ModTrash {
+    pub fn new(dir: impl AsRef<Path>) -> Self {
+        Self {
+            dir: dir.as_ref().to_owned(),
+        }
+    }
+    /// Moves the mod's directory, which has to be directly inside `local_mods`, into the trash.
+    pub fn trash(
+        &self,
+        mod_dir: impl AsRef<Path>,
+        local_mods: impl AsRef<Path>,
+        record: TrashRecord,
+    ) -> Result<TrashedMod> {
+        let mod_dir = mod_dir.as_ref();
+        let is_mod_dir = mod_dir.parent() == Some(local_mods.as_ref())
+            && mod_dir
+                .file_name()
+                .and_then(|it| it.to_str())
+                .is_some_and(is_mod_dir_name);
+        if !is_mod_dir {
+            bail!("{:?} isn't a mod in the game's mods directory", mod_dir)
+        }
+        if !mod_dir.is_dir() {
+            bail!("Mod directory {:?} doesn't exist", mod_dir)
+        }
+
+        let time = chrono::Local::now().naive_local();
+        let id = time.format(TIME_FORMAT).to_string();
+        let path = self.dir.join(&id);
+        fs::create_dir_all(&path)?;
+
+        let res = (|| -> Result<()> {
+            confy::store_path(path.join(RECORD_NAME), &record).map_err(eyre::Report::msg)?;
+            move_dir(mod_dir, &path.join(MOD_DIR))
+        })();
+        if let Err(err) = res {
+            _ = fs::remove_dir_all(&path).inspect_err(|e| warn!(%e));
+            return Err(err);
+        }
+        info!(?mod_dir, id, "Moved mod to the trash");
+
+        Ok(TrashedMod {
+            id,
+            path,
+            time,
+            record,
+        })
+    }
+    /// Trashed mods sorted from newest to oldest.
+    pub fn list(&self) -> Result<Vec<TrashedMod>> {
+        if !self.dir.is_dir() {
+            return Ok(vec![]);
+        }
+
+        Ok(WalkDir::new(&self.dir)
+            .max_depth(1)
+            .min_depth(1)
+            .into_iter()
+            .flat_map(|it| it.ok())
+            .filter(|it| it.file_type().is_dir())
+            .filter_map(|it| {
+                let id = it.file_name().to_str()?.to_owned();
+                let time = NaiveDateTime::parse_from_str(&id, TIME_FORMAT).ok()?;
+                let path = it.into_path();
+                let record = fs::read_to_string(path.join(RECORD_NAME))
+                    .map_err(eyre::Report::from)
+                    .and_then(|it| Ok(toml::from_str::<TrashRecord>(&it)?))
+                    .inspect_err(|err| warn!(?path, %err, "Couldn't read trashed mod"))
+                    .ok()?;
+                Some(TrashedMod {
+                    id,
+                    path,
+                    time,
+                    record,
+                })
+            })
+            .sorted_by(|a, b| Ord::cmp(&b.time, &a.time))
+            .collect_vec())
+    }
+    /// Finds a trashed mod by its id, or the latest one trashed under that mod name.
+    pub fn find(&self, query: impl AsRef<str>) -> Result<TrashedMod> {
+        let query = query.as_ref();
+        let list = self.list()?;
+        list.iter()
+            .find(|it| it.id == query)
+            .or_else(|| list.iter().find(|it| it.record.name == query))
+            .cloned()
+            .with_context(|| format!("No mod named '{}' in the trash", query))
+    }
+    /// Moves the mod back into `local_mods`, returning where it was put.
+    pub fn restore(&self, trashed: &TrashedMod, local_mods: impl AsRef<Path>) -> Result<PathBuf> {
+        if !is_mod_dir_name(&trashed.record.name) {
+            bail!("Trashed mod has an invalid name '{}'", trashed.record.name)
+        }
+        let target = local_mods.as_ref().join(&trashed.record.name);
+        if target.exists() {
+            bail!(
+                "Mod '{}' is installed again at {:?}, uninstall it first",
+                trashed.record.name,
+                target
+            )
+        }
+        move_dir(&trashed.mod_dir(), &target)?;
+        self.remove(trashed)?;
+        info!(id = trashed.id, ?target, "Restored mod from the trash");
+
+        Ok(target)
+    }
+    /// Deletes the trashed mod for good.
+    pub fn remove(&self, trashed: &TrashedMod) -> Result<()> {
+        fs::remove_dir_all(&trashed.path)?;
+        Ok(())
+    }
+    /// Deletes every trashed mod for good, returning how many there were.
+    pub fn empty(&self) -> Result<usize> {
+        let list = self.list()?;
+        for trashed in list.iter() {
+            self.remove(trashed)?;
+        }
+
+        Ok(list.len())
+    }
+}
+
+/// Renames the directory, or copies it over if that's not possible, like when `to` is on another filesystem.
+fn move_dir(from: &Path, to: &Path) -> Result<()> {
+    if fs::rename(from, to).is_ok() {
+        return Ok(());
+    }
+
+    let res = from.copy_dir(to, |_| false);
+    if let Err(err) = res {
+        _ = fs::remove_dir_all(to).inspect_err(|e| warn!(%e));
+        return Err(err);
+    }
+    fs::remove_dir_all(from)?;
+
+    Ok(())
+}
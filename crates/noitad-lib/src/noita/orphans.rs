@@ -0,0 +1,54 @@
+use std::path::PathBuf;
+
+use super::{
+    mod_config::{Mod, ModId, Mods},
+    NoitaPath,
+};
+
+/// Directories mods are installed in, to tell which entries of a mod list have nothing behind them
+/// anymore, e.g. after being unsubscribed from or deleted from `mods/`.
+#[derive(Debug, Clone, Default)]
+pub struct InstalledMods {
+    local_mods: Option<PathBuf>,
+    workshop: Option<PathBuf>,
+}
+
+impl InstalledMods {
+    pub fn new(noita_path: &NoitaPath) -> Self {
+        Self {
+            local_mods: noita_path.local_mods().filter(|it| it.is_dir()),
+            workshop: noita_path.workshop().filter(|it| it.is_dir()),
+        }
+    }
+    /// Folder the mod is or would be installed in, `None` if its root can't be found.
+    pub fn mod_dir(&self, mod_: &Mod) -> Option<PathBuf> {
+        match mod_.is_local() {
+            true => self.local_mods.as_ref().map(|it| it.join(&mod_.name)),
+            false => self
+                .workshop
+                .as_ref()
+                .map(|it| it.join(mod_.workshop_item_id.to_string())),
+        }
+    }
+    /// Whether the mod's folder exists, `None` if there's no telling, like for workshop mods
+    /// when the workshop can't be found.
+    pub fn is_installed(&self, mod_: &Mod) -> Option<bool> {
+        self.mod_dir(mod_).map(|it| it.is_dir())
+    }
+    /// Entries of `mod_list` whose mod is known to be gone.
+    pub fn orphans(&self, mod_list: &Mods) -> Vec<ModId> {
+        mod_list
+            .mods
+            .iter()
+            .filter(|it| self.is_installed(it) == Some(false))
+            .map(|it| it.id())
+            .collect()
+    }
+    /// Removes the entries of `mod_list` whose mod is known to be gone, returning them.
+    pub fn prune(&self, mod_list: &mut Mods) -> Vec<ModId> {
+        let orphans = self.orphans(mod_list);
+        mod_list.mods.retain(|it| !orphans.contains(&it.id()));
+
+        orphans
+    }
+}
@@ -0,0 +1,122 @@
+use std::path::Path;
+
+use color_eyre::eyre::{bail, ContextCompat, Result};
+use fs_err as fs;
+use serde::{Deserialize, Serialize};
+use tracing::{info, warn};
+
+use crate::ext::PathExt;
+
+use super::{
+    mod_catalog::is_mod_dir_name,
+    mod_config::{Mod, ModId, Mods},
+};
+
+/// Workshop mod that a profile uses a local copy of instead, so that workshop updates don't reach it.
+///
+/// The copy keeps the mod's name, as its scripts refer to their own files through `mods/<name>/`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PinnedMod {
+    pub name: String,
+    pub workshop_item_id: usize,
+}
+
+impl PinnedMod {
+    pub fn local_id(&self) -> ModId {
+        ModId {
+            name: self.name.clone(),
+            workshop_item_id: 0,
+        }
+    }
+    pub fn workshop_id(&self) -> ModId {
+        ModId {
+            name: self.name.clone(),
+            workshop_item_id: self.workshop_item_id,
+        }
+    }
+    /// Copies the workshop mod into `local_mods` and puts the copy in its place in `mod_list`.
+    ///
+    /// An existing copy is reused if `reuse_copy` is set, like when another profile pinned the mod already.
+    pub fn pin(
+        mod_list: &mut Mods,
+        workshop_item_id: usize,
+        workshop: impl AsRef<Path>,
+        local_mods: impl AsRef<Path>,
+        reuse_copy: bool,
+    ) -> Result<Self> {
+        let position = mod_list
+            .mods
+            .iter()
+            .position(|it| it.workshop_item_id == workshop_item_id && !it.is_local())
+            .with_context(|| format!("Workshop mod {} isn't in the profile", workshop_item_id))?;
+        let pinned = Self {
+            name: mod_list.mods[position].name.clone(),
+            workshop_item_id,
+        };
+        // The copy goes into the mods directory under this name
+        if !is_mod_dir_name(&pinned.name) {
+            bail!(
+                "Workshop mod {} has an invalid name '{}'",
+                workshop_item_id,
+                pinned.name
+            )
+        }
+        if mod_list.position(&pinned.local_id()).is_some() {
+            bail!(
+                "The profile already has a local mod named '{}'",
+                pinned.name
+            )
+        }
+
+        let source = workshop.as_ref().join(workshop_item_id.to_string());
+        let target = local_mods.as_ref().join(&pinned.name);
+        if !source.is_dir() {
+            bail!("Workshop mod {} isn't downloaded", workshop_item_id)
+        }
+        match (target.exists(), reuse_copy) {
+            (true, true) => {}
+            (true, false) => bail!(
+                "A local mod named '{}' is already installed at {:?}",
+                pinned.name,
+                target
+            ),
+            (false, _) => {
+                if let Err(err) = source.copy_dir(&target, |_| false) {
+                    _ = fs::remove_dir_all(&target).inspect_err(|e| warn!(%e));
+                    return Err(err);
+                }
+                info!(?source, ?target, "Copied workshop mod");
+            }
+        }
+
+        let workshop = &mod_list.mods[position];
+        let mut local = Mod::new(&pinned.name, 0);
+        local.enabled = workshop.enabled;
+        local.settings_fold_open = workshop.settings_fold_open;
+        mod_list.mods[position] = local;
+
+        Ok(pinned)
+    }
+    /// Puts the workshop mod back in place of its local copy in `mod_list`, the copy itself is left as is.
+    pub fn unpin(&self, mod_list: &mut Mods) -> Result<()> {
+        let position = mod_list
+            .position(&self.local_id())
+            .with_context(|| format!("Local copy of '{}' isn't in the profile", self.name))?;
+        let local = &mod_list.mods[position];
+        let mut workshop = Mod::new(&self.name, self.workshop_item_id);
+        workshop.enabled = local.enabled;
+        workshop.settings_fold_open = local.settings_fold_open;
+        mod_list.mods[position] = workshop;
+        // The game might've added the workshop entry back on its own in the meantime
+        if let Some(i) = mod_list
+            .mods
+            .iter()
+            .enumerate()
+            .position(|(i, it)| i != position && it.id() == self.workshop_id())
+        {
+            mod_list.mods.remove(i);
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,68 @@
+use std::{path::Path, thread, time::Duration};
+
+use fs_err as fs;
+
+/// Executable names of the game, including the dev build shipped alongside it.
+const NOITA_EXECUTABLES: [&str; 2] = ["noita.exe", "noita_dev.exe"];
+
+/// A running instance of the game.
+///
+/// Noita rewrites `mod_config.xml` when it exits, so anything written to it while the game is
+/// running gets lost.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct NoitaProcess {
+    pub pid: u32,
+}
+
+impl NoitaProcess {
+    /// Looks for the game among running processes, either native or under Wine/Proton.
+    #[cfg(target_os = "linux")]
+    pub fn find() -> Option<Self> {
+        let own_pid = std::process::id();
+        fs::read_dir("/proc")
+            .ok()?
+            .flat_map(|it| it.ok())
+            .filter_map(|it| it.file_name().to_str()?.parse::<u32>().ok())
+            .filter(|pid| *pid != own_pid)
+            .find(|pid| is_noita_cmdline(*pid) || is_noita_comm(*pid))
+            .map(|pid| Self { pid })
+    }
+    #[cfg(not(target_os = "linux"))]
+    pub fn find() -> Option<Self> {
+        None
+    }
+    pub fn is_running(&self) -> bool {
+        Path::new("/proc").join(self.pid.to_string()).exists()
+    }
+    /// Blocks until the process exits.
+    pub fn wait(&self) {
+        while self.is_running() {
+            thread::sleep(Duration::from_secs(1));
+        }
+    }
+}
+
+fn is_noita_executable(path: &str) -> bool {
+    // Wine processes can have a Windows style path as their argv[0]
+    let name = path.rsplit(['/', '\\']).next().unwrap_or(path);
+    NOITA_EXECUTABLES
+        .iter()
+        .any(|it| name.eq_ignore_ascii_case(it))
+}
+
+/// Only argv[0] is looked at, launchers like Proton or Steam pass the game's path along as an argument.
+fn is_noita_cmdline(pid: u32) -> bool {
+    fs::read(format!("/proc/{pid}/cmdline"))
+        .ok()
+        .and_then(|cmdline| {
+            let argv0 = cmdline.split(|b| *b == 0).next()?;
+            Some(is_noita_executable(&String::from_utf8_lossy(argv0)))
+        })
+        .unwrap_or_default()
+}
+
+fn is_noita_comm(pid: u32) -> bool {
+    fs::read_to_string(format!("/proc/{pid}/comm"))
+        .map(|comm| is_noita_executable(comm.trim()))
+        .unwrap_or_default()
+}
@@ -0,0 +1,145 @@
+use std::path::{Path, PathBuf};
+
+use color_eyre::eyre::{bail, Result};
+use fs_err as fs;
+use tracing::{info, warn};
+use walkdir::WalkDir;
+
+use super::{
+    process::NoitaProcess,
+    save_snapshots::{SaveSnapshot, SaveSnapshots},
+};
+
+const SAVES_DIR: &str = "noitad_profile_saves";
+/// Slot of the save used by all profiles that don't have one of their own.
+///
+/// Profile ids can't start with an underscore, so it never clashes with one.
+pub const SHARED_SLOT: &str = "_shared";
+
+/// Saves of the profiles that aren't in use right now, kept in a directory next to the save directory
+/// so that swapping them in and out is just a rename.
+///
+/// `mod_config.xml` is left out of them, since it's what the profiles themselves take care of.
+#[derive(Debug, Clone)]
+pub struct ProfileSaves {
+    noita_save_dir: PathBuf,
+    dir: PathBuf,
+}
+
+impl ProfileSaves {
+    pub fn new(noita_save_dir: impl AsRef<Path>) -> Self {
+        let noita_save_dir = noita_save_dir.as_ref().to_owned();
+        let dir = noita_save_dir
+            .parent()
+            .unwrap_or(&noita_save_dir)
+            .join(SAVES_DIR);
+
+        Self {
+            noita_save_dir,
+            dir,
+        }
+    }
+    pub fn slot_path(&self, slot: &str) -> PathBuf {
+        self.dir.join(slot)
+    }
+    /// Stores the current save under the `from` slot and puts the one stored under `to` in its place,
+    /// a slot without a stored save starts out as a new game.
+    pub fn swap(&self, from: &str, to: &str) -> Result<()> {
+        if from == to {
+            return Ok(());
+        }
+        ensure_noita_closed()?;
+
+        let from_path = self.slot_path(from);
+        let to_path = self.slot_path(to);
+        if from_path.exists() {
+            bail!("A save is already stored for '{}' at {:?}", from, from_path)
+        }
+        fs::create_dir_all(&to_path)?;
+
+        let mod_config = self.noita_save_dir.join("mod_config.xml");
+        let moved_mod_config = to_path.join("mod_config.xml");
+        let has_mod_config = mod_config.is_file();
+        if has_mod_config {
+            fs::rename(&mod_config, &moved_mod_config)?;
+        }
+        let undo_mod_config = || {
+            if has_mod_config {
+                _ = fs::rename(&moved_mod_config, &mod_config).inspect_err(|e| warn!(%e));
+            }
+        };
+
+        if let Err(err) = fs::rename(&self.noita_save_dir, &from_path) {
+            undo_mod_config();
+            return Err(err.into());
+        }
+        if let Err(err) = fs::rename(&to_path, &self.noita_save_dir) {
+            _ = fs::rename(&from_path, &self.noita_save_dir).inspect_err(|e| warn!(%e));
+            undo_mod_config();
+            return Err(err.into());
+        }
+        info!(from, to, "Swapped save");
+
+        Ok(())
+    }
+    /// Copies the current save into a slot that doesn't have a stored save yet.
+    pub fn copy_to(&self, slot: &str) -> Result<()> {
+        ensure_noita_closed()?;
+
+        let path = self.slot_path(slot);
+        if path.exists() {
+            bail!("A save is already stored for '{}' at {:?}", slot, path)
+        }
+
+        // Copied under a temporary name first, so that an interrupted copy isn't mistaken for a save
+        let tmp = self.dir.join(format!(".{slot}.tmp"));
+        if tmp.exists() {
+            fs::remove_dir_all(&tmp)?;
+        }
+        for entry in WalkDir::new(&self.noita_save_dir).min_depth(1) {
+            let entry = entry?;
+            let relative = entry.path().strip_prefix(&self.noita_save_dir)?;
+            if relative == Path::new("mod_config.xml") {
+                continue;
+            }
+
+            let target = tmp.join(relative);
+            if entry.file_type().is_dir() {
+                fs::create_dir_all(&target)?;
+            } else {
+                if let Some(parent) = target.parent() {
+                    fs::create_dir_all(parent)?;
+                }
+                fs::copy(entry.path(), &target)?;
+            }
+        }
+        fs::create_dir_all(&tmp)?;
+        fs::rename(&tmp, &path)?;
+        info!(slot, "Copied save");
+
+        Ok(())
+    }
+    /// Removes the save stored under `slot`, keeping it as a [`SaveSnapshots`] snapshot in case it's needed again.
+    pub fn remove(&self, slot: &str, label: String) -> Result<Option<SaveSnapshot>> {
+        let path = self.slot_path(slot);
+        if !path.is_dir() {
+            return Ok(None);
+        }
+
+        let snapshot = SaveSnapshots::default().create(&path, Some(label))?;
+        fs::remove_dir_all(&path)?;
+        info!(slot, id = snapshot.id, "Removed save");
+
+        Ok(Some(snapshot))
+    }
+}
+
+fn ensure_noita_closed() -> Result<()> {
+    if let Some(process) = NoitaProcess::find() {
+        bail!(
+            "Noita is running (pid {}), close the game before switching saves",
+            process.pid
+        )
+    }
+    Ok(())
+}
@@ -0,0 +1,368 @@
+pub mod blob_store;
+
+use std::{
+    collections::HashSet,
+    fmt,
+    path::{Path, PathBuf},
+};
+
+use blob_store::{BlobStore, GcReport};
+use chrono::NaiveDateTime;
+use color_eyre::eyre::{self, bail, ContextCompat, Result, WrapErr};
+use fs_err as fs;
+use itertools::Itertools;
+use serde::{Deserialize, Serialize};
+use tracing::{info, warn};
+use walkdir::WalkDir;
+
+use crate::{defines::SAVE_SNAPSHOTS_DIR, ext::PathExt};
+
+use super::process::NoitaProcess;
+
+const META_NAME: &str = "snapshot.toml";
+const MANIFEST_NAME: &str = "manifest.toml";
+const MOD_CONFIG_NAME: &str = "mod_config.xml";
+const BLOBS_DIR: &str = "blobs";
+const TIME_FORMAT: &str = "%Y-%m-%dT%H-%M-%S%.3f";
+
+/// Snapshots of the whole save directory, stored under [`SAVE_SNAPSHOTS_DIR`].
+///
+/// A snapshot only lists the files of the save, their contents are kept in a [`BlobStore`]
+/// shared by all snapshots, so that files which didn't change between snapshots are stored once.
+#[derive(Debug, Clone)]
+pub struct SaveSnapshots {
+    dir: PathBuf,
+    blobs: BlobStore,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SnapshotMeta {
+    pub label: Option<String>,
+    /// Total size of the files in the snapshot.
+    pub size: u64,
+}
+
+/// Files and directories that make up a snapshot, paths are relative to the save directory.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+struct Manifest {
+    dirs: Vec<String>,
+    files: Vec<ManifestFile>,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+struct ManifestFile {
+    path: String,
+    size: u64,
+    /// Hashes of the file's chunks in the [`BlobStore`].
+    chunks: Vec<String>,
+}
+
+/// Space taken up by the snapshots.
+#[derive(Debug, Clone, Default)]
+pub struct StorageUsage {
+    /// What the snapshots would take up if each of them was a full copy of the save.
+    pub logical: u64,
+    /// What the snapshots actually take up on disk.
+    pub stored: u64,
+}
+
+#[derive(Debug, Clone)]
+pub struct SaveSnapshot {
+    /// Name the snapshot is referred to by, its timestamp.
+    pub id: String,
+    pub path: PathBuf,
+    pub time: NaiveDateTime,
+    pub meta: SnapshotMeta,
+}
+
+impl SaveSnapshot {
+    /// Size of the save in the snapshot, in bytes.
+    pub fn size(&self) -> u64 {
+        self.meta.size
+    }
+    /// Whether the snapshot was written out completely, the manifest is the last thing written.
+    fn is_complete(&self) -> bool {
+        self.path.join(MANIFEST_NAME).is_file()
+    }
+    fn manifest(&self) -> Result<Manifest> {
+        Manifest::read(&self.path)
+    }
+}
+
+impl Manifest {
+    fn read(snapshot_dir: &Path) -> Result<Self> {
+        let path = snapshot_dir.join(MANIFEST_NAME);
+        toml::from_str(&fs::read_to_string(&path)?)
+            .with_context(|| format!("Couldn't parse the manifest {:?}", path))
+    }
+}
+
+pub fn format_size(bytes: u64) -> String {
+    format!("{:.1} MiB", bytes as f64 / (1024. * 1024.))
+}
+
+impl fmt::Display for SaveSnapshot {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.time.format("%Y-%m-%d %H:%M:%S"))?;
+        if let Some(label) = &self.meta.label {
+            write!(f, " {label}")?;
+        }
+        write!(f, " ({})", format_size(self.size()))
+    }
+}
+
+impl Default for SaveSnapshots {
+    fn default() -> Self {
+        Self::new(SAVE_SNAPSHOTS_DIR.as_path())
+    }
+}
+
+impl SaveSnapshots {
+    pub fn new(dir: impl AsRef<Path>) -> Self {
+        Self {
+            dir: dir.as_ref().to_owned(),
+            blobs: BlobStore::new(dir.as_ref().join(BLOBS_DIR)),
+        }
+    }
+    /// Snapshots the save directory as it is right now.
+    pub fn create(
+        &self,
+        noita_save_dir: impl AsRef<Path>,
+        label: Option<String>,
+    ) -> Result<SaveSnapshot> {
+        let save_dir = noita_save_dir.as_ref();
+        if !save_dir.is_dir() {
+            bail!("Save directory {:?} doesn't exist", save_dir)
+        }
+
+        let time = chrono::Local::now().naive_local();
+        let id = time.format(TIME_FORMAT).to_string();
+        let path = self.dir.join(&id);
+        fs::create_dir_all(&path)?;
+
+        let mut meta = SnapshotMeta {
+            label,
+            ..Default::default()
+        };
+        let res = (|| -> Result<()> {
+            let mut manifest = Manifest::default();
+            for entry in WalkDir::new(save_dir).min_depth(1).sort_by_file_name() {
+                let entry = entry?;
+                let relative = entry
+                    .path()
+                    .strip_prefix(save_dir)?
+                    .to_str()
+                    .with_context(|| format!("{:?} isn't a valid UTF-8 path", entry.path()))?
+                    .replace(std::path::MAIN_SEPARATOR, "/");
+
+                if entry.file_type().is_dir() {
+                    manifest.dirs.push(relative);
+                } else if entry.file_type().is_file() {
+                    let size = entry.metadata()?.len();
+                    meta.size += size;
+                    manifest.files.push(ManifestFile {
+                        path: relative,
+                        size,
+                        chunks: self.blobs.store_file(entry.path())?,
+                    });
+                }
+            }
+
+            confy::store_path(path.join(META_NAME), &meta).map_err(eyre::Report::msg)?;
+            // Written last, a snapshot without a manifest is one that wasn't finished
+            path.join(MANIFEST_NAME)
+                .write_atomic(toml::to_string(&manifest)?)
+        })();
+        if let Err(err) = res {
+            // Don't leave a partial snapshot behind that'd look like a valid one
+            _ = fs::remove_dir_all(&path).inspect_err(|e| warn!(%e));
+            return Err(err);
+        }
+        info!(?path, "Created save snapshot");
+
+        Ok(SaveSnapshot {
+            id,
+            path,
+            time,
+            meta,
+        })
+    }
+    /// Snapshots sorted from newest to oldest.
+    pub fn list(&self) -> Result<Vec<SaveSnapshot>> {
+        if !self.dir.is_dir() {
+            return Ok(vec![]);
+        }
+
+        Ok(WalkDir::new(&self.dir)
+            .max_depth(1)
+            .min_depth(1)
+            .into_iter()
+            .flat_map(|it| it.ok())
+            .filter(|it| it.file_type().is_dir())
+            .filter_map(|it| {
+                let id = it.file_name().to_str()?.to_owned();
+                let time = NaiveDateTime::parse_from_str(&id, TIME_FORMAT).ok()?;
+                let path = it.into_path();
+                let meta = fs::read_to_string(path.join(META_NAME))
+                    .map_err(eyre::Report::from)
+                    .and_then(|it| Ok(toml::from_str::<SnapshotMeta>(&it)?))
+                    .inspect_err(|err| warn!(?path, %err, "Skipping incomplete snapshot"))
+                    .ok()?;
+                Some(SaveSnapshot {
+                    id,
+                    path,
+                    time,
+                    meta,
+                })
+                .filter(|it| it.is_complete())
+            })
+            .sorted_by(|a, b| Ord::cmp(&b.time, &a.time))
+            .collect_vec())
+    }
+    pub fn find(&self, id: impl AsRef<str>) -> Result<SaveSnapshot> {
+        self.list()?
+            .into_iter()
+            .find(|it| it.id == id.as_ref())
+            .with_context(|| format!("No save snapshot named '{}'", id.as_ref()))
+    }
+    /// Replaces the save directory with the snapshot's contents.
+    ///
+    /// The game's `mod_config.xml` is kept as is, as it follows the active profile rather than the save.
+    ///
+    /// The save being replaced is snapshotted first, that safety snapshot is returned if there was a save to keep.
+    pub fn restore(
+        &self,
+        snapshot: &SaveSnapshot,
+        noita_save_dir: impl AsRef<Path>,
+    ) -> Result<Option<SaveSnapshot>> {
+        if let Some(process) = NoitaProcess::find() {
+            bail!(
+                "Noita is running (pid {}), close the game before restoring a save",
+                process.pid
+            )
+        }
+
+        let save_dir = noita_save_dir.as_ref();
+        let parent = save_dir
+            .parent()
+            .context("Save directory has no parent directory")?;
+        let safety = match save_dir.is_dir() {
+            true => Some(self.create(save_dir, Some(format!("Before restoring {}", snapshot.id)))?),
+            false => None,
+        };
+
+        // Unpacked next to the save first, so that a failure midway leaves the current save as is
+        let staging = parent.join(".noitad_restore");
+        if staging.exists() {
+            fs::remove_dir_all(&staging)?;
+        }
+        fs::create_dir_all(&staging)?;
+        let old = parent.join(".noitad_restore_old");
+        let res = (|| -> Result<()> {
+            let manifest = snapshot.manifest()?;
+            for dir in manifest.dirs.iter() {
+                fs::create_dir_all(staging.join(safe_relative_path(dir)?))?;
+            }
+            for file in manifest
+                .files
+                .iter()
+                .filter(|it| it.path != MOD_CONFIG_NAME)
+            {
+                let path = staging.join(safe_relative_path(&file.path)?);
+                if let Some(parent) = path.parent() {
+                    fs::create_dir_all(parent)?;
+                }
+                self.blobs.restore_file(&file.chunks, path)?;
+            }
+            let mod_config = save_dir.join(MOD_CONFIG_NAME);
+            if mod_config.is_file() {
+                fs::copy(&mod_config, staging.join(MOD_CONFIG_NAME))?;
+            }
+
+            if old.exists() {
+                fs::remove_dir_all(&old)?;
+            }
+            if save_dir.exists() {
+                fs::rename(save_dir, &old)?;
+            }
+            Ok(())
+        })();
+        if let Err(err) = res {
+            _ = fs::remove_dir_all(&staging).inspect_err(|e| warn!(%e));
+            return Err(err);
+        }
+
+        if let Err(err) = fs::rename(&staging, save_dir) {
+            _ = fs::remove_dir_all(&staging).inspect_err(|e| warn!(%e));
+            if old.exists() {
+                _ = fs::rename(&old, save_dir).inspect_err(|e| warn!(%e));
+            }
+            return Err(err.into());
+        }
+        if old.exists() {
+            _ = fs::remove_dir_all(&old).inspect_err(|e| warn!(%e));
+        }
+        info!(id = snapshot.id, "Restored save snapshot");
+
+        Ok(safety)
+    }
+    /// Removes the snapshot, the space used by its files is only freed by [`SaveSnapshots::gc`].
+    pub fn remove(&self, snapshot: &SaveSnapshot) -> Result<()> {
+        fs::remove_dir_all(&snapshot.path)?;
+        Ok(())
+    }
+    /// Removes the stored file contents that no snapshot refers to anymore.
+    pub fn gc(&self) -> Result<GcReport> {
+        if !self.dir.is_dir() {
+            return Ok(GcReport::default());
+        }
+
+        // Every snapshot directory counts, not just the ones that can be listed, as it's better to
+        // keep everything than to throw away what an unreadable snapshot might need
+        let mut referenced = HashSet::new();
+        for entry in WalkDir::new(&self.dir).min_depth(1).max_depth(1) {
+            let entry = entry?;
+            if !entry.file_type().is_dir() || entry.file_name() == BLOBS_DIR {
+                continue;
+            }
+            let manifest = Manifest::read(entry.path()).with_context(|| {
+                format!("Couldn't read the manifest of snapshot {:?}", entry.path())
+            })?;
+            referenced.extend(manifest.files.into_iter().flat_map(|it| it.chunks));
+        }
+
+        let report = self.blobs.gc(&referenced)?;
+        info!(?report, "Collected unused blobs");
+
+        Ok(report)
+    }
+    pub fn usage(&self) -> Result<StorageUsage> {
+        let logical = self.list()?.iter().map(|it| it.size()).sum();
+        let stored = match self.dir.is_dir() {
+            true => WalkDir::new(&self.dir)
+                .into_iter()
+                .flat_map(|it| it.ok())
+                .filter(|it| it.file_type().is_file())
+                .flat_map(|it| it.metadata().ok())
+                .map(|it| it.len())
+                .sum(),
+            false => 0,
+        };
+
+        Ok(StorageUsage { logical, stored })
+    }
+}
+
+/// Guards against a manifest pointing outside of the directory it's restored into.
+fn safe_relative_path(path: &str) -> Result<&Path> {
+    let path = Path::new(path);
+    if !path
+        .components()
+        .all(|it| matches!(it, std::path::Component::Normal(_)))
+    {
+        bail!("Snapshot contains an invalid path {:?}", path)
+    }
+    Ok(path)
+}
@@ -0,0 +1,253 @@
+use std::{
+    collections::HashSet,
+    io::{Read, Write},
+    path::{Path, PathBuf},
+};
+
+use color_eyre::eyre::{bail, Result};
+use flate2::{read::GzDecoder, write::GzEncoder, Compression};
+use fs_err as fs;
+use walkdir::WalkDir;
+
+/// Files are split into chunks of this size, so that a change to a large file only adds the chunks that changed.
+pub const CHUNK_SIZE: u64 = 1024 * 1024;
+
+/// Compressed chunks of file contents, stored once under their hash no matter how many snapshots use them.
+#[derive(Debug, Clone)]
+pub struct BlobStore {
+    dir: PathBuf,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct GcReport {
+    pub removed: usize,
+    /// Bytes freed on disk.
+    pub freed: u64,
+}
+
+impl BlobStore {
+    pub fn new(dir: impl AsRef<Path>) -> Self {
+        Self {
+            dir: dir.as_ref().to_owned(),
+        }
+    }
+    /// Stores the file's chunks that aren't stored yet, returns the hashes of all of its chunks in order.
+    pub fn store_file(&self, path: impl AsRef<Path>) -> Result<Vec<String>> {
+        let mut file = fs::File::open(path.as_ref())?;
+        let mut chunks = vec![];
+        let mut buf = Vec::with_capacity(CHUNK_SIZE as usize);
+        loop {
+            buf.clear();
+            (&mut file).take(CHUNK_SIZE).read_to_end(&mut buf)?;
+            if buf.is_empty() {
+                break;
+            }
+            chunks.push(self.store_chunk(&buf)?);
+        }
+
+        Ok(chunks)
+    }
+    /// Puts a file back together from its chunks, each of them is checked against its hash.
+    pub fn restore_file(&self, chunks: &[String], to: impl AsRef<Path>) -> Result<()> {
+        let mut file = fs::File::create(to.as_ref())?;
+        let mut buf = Vec::with_capacity(CHUNK_SIZE as usize);
+        for hash in chunks {
+            if hash.len() != blake3::OUT_LEN * 2 || !hash.chars().all(|it| it.is_ascii_hexdigit()) {
+                bail!("Invalid blob hash '{}'", hash)
+            }
+            buf.clear();
+            GzDecoder::new(fs::File::open(self.path(hash))?).read_to_end(&mut buf)?;
+            if blake3::hash(&buf).to_hex().as_str() != hash {
+                bail!("Blob {} is corrupted", hash)
+            }
+            file.write_all(&buf)?;
+        }
+
+        Ok(())
+    }
+    /// Removes every blob that isn't in `referenced`.
+    pub fn gc(&self, referenced: &HashSet<String>) -> Result<GcReport> {
+        let mut report = GcReport::default();
+        if !self.dir.is_dir() {
+            return Ok(report);
+        }
+
+        for entry in WalkDir::new(&self.dir).min_depth(2).max_depth(2) {
+            let entry = entry?;
+            if !entry.file_type().is_file() {
+                continue;
+            }
+            let hash = format!(
+                "{}{}",
+                entry
+                    .path()
+                    .parent()
+                    .and_then(|it| it.file_name())
+                    .unwrap_or_default()
+                    .to_string_lossy(),
+                entry.file_name().to_string_lossy()
+            );
+            if referenced.contains(&hash) {
+                continue;
+            }
+
+            let size = entry.metadata()?.len();
+            fs::remove_file(entry.path())?;
+            report.removed += 1;
+            report.freed += size;
+        }
+
+        Ok(report)
+    }
+    fn store_chunk(&self, data: &[u8]) -> Result<String> {
+        let hash = blake3::hash(data).to_hex().to_string();
+        let path = self.path(&hash);
+        if path.is_file() {
+            return Ok(hash);
+        }
+
+        let parent = path.parent().expect("blobs are stored in a subdirectory");
+        fs::create_dir_all(parent)?;
+        // Written under a temporary name first, so that a blob is never seen partially written
+        let tmp = parent.join(format!(".{hash}.tmp"));
+        let mut encoder = GzEncoder::new(fs::File::create(&tmp)?, Compression::fast());
+        encoder.write_all(data)?;
+        encoder.finish()?.sync_all()?;
+        fs::rename(&tmp, &path)?;
+
+        Ok(hash)
+    }
+    fn path(&self, hash: &str) -> PathBuf {
+        self.dir.join(&hash[..2]).join(&hash[2..])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Empty directory for a test, removed again once the test is done with it.
+    struct TestDir(PathBuf);
+
+    impl TestDir {
+        fn new(name: &str) -> Self {
+            let dir = std::env::temp_dir()
+                .join(format!("noitad-blob-store-{}-{name}", std::process::id()));
+            _ = fs::remove_dir_all(&dir);
+            fs::create_dir_all(&dir).unwrap();
+            Self(dir)
+        }
+        fn write(&self, name: &str, contents: &[u8]) -> PathBuf {
+            let path = self.0.join(name);
+            fs::write(&path, contents).unwrap();
+            path
+        }
+    }
+
+    impl Drop for TestDir {
+        fn drop(&mut self) {
+            _ = fs::remove_dir_all(&self.0);
+        }
+    }
+
+    /// Contents that differ from chunk to chunk.
+    fn contents(len: usize, seed: u8) -> Vec<u8> {
+        (0..len)
+            .map(|i| (i as u64 * 31 + i as u64 / 7919) as u8 ^ seed)
+            .collect()
+    }
+
+    fn blob_count(store: &BlobStore) -> usize {
+        WalkDir::new(&store.dir)
+            .min_depth(2)
+            .max_depth(2)
+            .into_iter()
+            .filter(|it| it.as_ref().unwrap().file_type().is_file())
+            .count()
+    }
+
+    #[test]
+    fn round_trips_files_across_chunks() {
+        let dir = TestDir::new("round-trip");
+        let store = BlobStore::new(dir.0.join("blobs"));
+        let data = contents(2 * CHUNK_SIZE as usize + 123, 0);
+
+        let chunks = store.store_file(dir.write("file", &data)).unwrap();
+        assert_eq!(chunks.len(), 3);
+
+        let restored = dir.0.join("restored");
+        store.restore_file(&chunks, &restored).unwrap();
+        assert!(fs::read(&restored).unwrap() == data);
+    }
+
+    #[test]
+    fn empty_file_has_no_chunks() {
+        let dir = TestDir::new("empty");
+        let store = BlobStore::new(dir.0.join("blobs"));
+
+        let chunks = store.store_file(dir.write("file", &[])).unwrap();
+        assert!(chunks.is_empty());
+
+        let restored = dir.0.join("restored");
+        store.restore_file(&chunks, &restored).unwrap();
+        assert!(fs::read(&restored).unwrap().is_empty());
+    }
+
+    #[test]
+    fn identical_chunks_are_stored_once() {
+        let dir = TestDir::new("dedup");
+        let store = BlobStore::new(dir.0.join("blobs"));
+        let data = vec![7; 2 * CHUNK_SIZE as usize];
+
+        let chunks = store.store_file(dir.write("a", &data)).unwrap();
+        assert_eq!(chunks[0], chunks[1]);
+        assert_eq!(store.store_file(dir.write("b", &data)).unwrap(), chunks);
+        assert_eq!(blob_count(&store), 1);
+    }
+
+    #[test]
+    fn rejects_corrupted_and_invalid_blobs() {
+        let dir = TestDir::new("corrupted");
+        let store = BlobStore::new(dir.0.join("blobs"));
+        let chunks = store.store_file(dir.write("a", b"original")).unwrap();
+        let other = store.store_file(dir.write("b", b"something else")).unwrap();
+        fs::copy(store.path(&other[0]), store.path(&chunks[0])).unwrap();
+
+        let restored = dir.0.join("restored");
+        assert!(store.restore_file(&chunks, &restored).is_err());
+        assert!(store
+            .restore_file(&["../../../etc/passwd".into()], &restored)
+            .is_err());
+    }
+
+    #[test]
+    fn gc_removes_only_unreferenced_blobs() {
+        let dir = TestDir::new("gc");
+        let store = BlobStore::new(dir.0.join("blobs"));
+        let kept = store
+            .store_file(dir.write("kept", &contents(CHUNK_SIZE as usize + 1, 1)))
+            .unwrap();
+        let dropped = store
+            .store_file(dir.write("dropped", &contents(10, 2)))
+            .unwrap();
+
+        let report = store.gc(&kept.iter().cloned().collect()).unwrap();
+        assert_eq!(report.removed, dropped.len());
+        assert!(report.freed > 0);
+        assert_eq!(blob_count(&store), kept.len());
+
+        store.restore_file(&kept, dir.0.join("restored")).unwrap();
+        assert!(store
+            .restore_file(&dropped, dir.0.join("restored"))
+            .is_err());
+    }
+
+    #[test]
+    fn gc_without_blobs_does_nothing() {
+        let dir = TestDir::new("gc-empty");
+        let report = BlobStore::new(dir.0.join("blobs"))
+            .gc(&HashSet::new())
+            .unwrap();
+        assert_eq!(report.removed, 0);
+    }
+}
@@ -0,0 +1,190 @@
+use std::path::{Path, PathBuf};
+
+use better_default::Default;
+use chrono::{NaiveDateTime, TimeDelta};
+use color_eyre::eyre::{self, Result};
+use fs_err as fs;
+use itertools::Itertools;
+use serde::{Deserialize, Serialize};
+use tracing::{info, warn};
+use walkdir::WalkDir;
+
+use crate::defines::SWITCH_SNAPSHOTS_DIR;
+
+use super::save_snapshots::SaveSnapshots;
+
+const RECORD_NAME: &str = "switch.toml";
+const MOD_CONFIG_NAME: &str = "mod_config.xml";
+const TIME_FORMAT: &str = "%Y-%m-%dT%H-%M-%S%.3f";
+
+/// When to take a snapshot of the game's state before switching profiles, and how long to keep it around.
+///
+/// Only profile switches are snapshotted, every other write to `mod_config.xml` leaves a backup of
+/// it in [`ModConfigBackups`](super::mod_config::backups::ModConfigBackups) instead.
+#[derive(Debug, Default, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SafetySnapshotPolicy {
+    #[default(true)]
+    pub enabled: bool,
+    /// Snapshot the whole save directory as well, not just `mod_config.xml`.
+    pub include_save: bool,
+    /// Number of snapshots kept, older ones are removed.
+    #[default(20)]
+    pub max_count: usize,
+    /// Snapshots older than this are removed, `0` keeps them regardless of their age.
+    #[default(30)]
+    pub max_age_days: u32,
+}
+
+/// What a profile switch replaced.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SwitchRecord {
+    /// Profile that was active before the switch.
+    pub from: Option<String>,
+    pub to: String,
+    /// Id of the snapshot in [`SaveSnapshots`] of the save before the switch, if it was taken.
+    pub save_snapshot: Option<String>,
+}
+
+#[derive(Debug, Clone)]
+pub struct SwitchSnapshot {
+    pub id: String,
+    pub path: PathBuf,
+    pub time: NaiveDateTime,
+    pub record: SwitchRecord,
+}
+
+impl SwitchSnapshot {
+    /// Copy of `mod_config.xml` from before the switch, if the game had one.
+    pub fn mod_config(&self) -> Option<PathBuf> {
+        Some(self.path.join(MOD_CONFIG_NAME)).filter(|it| it.is_file())
+    }
+}
+
+/// Game state from before profile switches, so that a switch can be undone.
+#[derive(Debug, Clone)]
+pub struct SwitchSnapshots {
+    dir: PathBuf,
+}
+
+impl Default for SwitchSnapshots {
+    fn default() -> Self {
+        Self::new(SWITCH_SNAPSHOTS_DIR.as_path())
+    }
+}
+
+impl SwitchSnapshots {
+    pub fn new(dir: impl AsRef<Path>) -> Self {
+        Self {
+            dir: dir.as_ref().to_owned(),
+        }
+    }
+    pub fn create(
+        &self,
+        noita_save_dir: impl AsRef<Path>,
+        from: Option<String>,
+        to: impl Into<String>,
+        include_save: bool,
+    ) -> Result<SwitchSnapshot> {
+        let save_dir = noita_save_dir.as_ref();
+        let time = chrono::Local::now().naive_local();
+        let id = time.format(TIME_FORMAT).to_string();
+        let path = self.dir.join(&id);
+        fs::create_dir_all(&path)?;
+
+        let mut record = SwitchRecord {
+            from,
+            to: to.into(),
+            save_snapshot: None,
+        };
+        let res = (|| -> Result<()> {
+            let mod_config = save_dir.join(MOD_CONFIG_NAME);
+            if mod_config.is_file() {
+                fs::copy(&mod_config, path.join(MOD_CONFIG_NAME))?;
+            }
+            if include_save {
+                let snapshot = SaveSnapshots::default()
+                    .create(save_dir, Some(format!("Before switching to {}", record.to)))?;
+                record.save_snapshot = Some(snapshot.id);
+            }
+
+            confy::store_path(path.join(RECORD_NAME), &record).map_err(eyre::Report::msg)
+        })();
+        if let Err(err) = res {
+            _ = fs::remove_dir_all(&path).inspect_err(|e| warn!(%e));
+            return Err(err);
+        }
+        info!(?path, "Created switch snapshot");
+
+        Ok(SwitchSnapshot {
+            id,
+            path,
+            time,
+            record,
+        })
+    }
+    /// Snapshots sorted from newest to oldest.
+    pub fn list(&self) -> Result<Vec<SwitchSnapshot>> {
+        if !self.dir.is_dir() {
+            return Ok(vec![]);
+        }
+
+        Ok(WalkDir::new(&self.dir)
+            .max_depth(1)
+            .min_depth(1)
+            .into_iter()
+            .flat_map(|it| it.ok())
+            .filter(|it| it.file_type().is_dir())
+            .filter_map(|it| {
+                let id = it.file_name().to_str()?.to_owned();
+                let time = NaiveDateTime::parse_from_str(&id, TIME_FORMAT).ok()?;
+                let path = it.into_path();
+                let record = fs::read_to_string(path.join(RECORD_NAME))
+                    .map_err(eyre::Report::from)
+                    .and_then(|it| Ok(toml::from_str::<SwitchRecord>(&it)?))
+                    .inspect_err(|err| warn!(?path, %err, "Couldn't read switch snapshot"))
+                    .ok()?;
+                Some(SwitchSnapshot {
+                    id,
+                    path,
+                    time,
+                    record,
+                })
+            })
+            .sorted_by(|a, b| Ord::cmp(&b.time, &a.time))
+            .collect_vec())
+    }
+    /// Removes the snapshot along with the save snapshot taken for it.
+    pub fn remove(&self, snapshot: &SwitchSnapshot) -> Result<()> {
+        if let Some(id) = &snapshot.record.save_snapshot {
+            let save_snapshots = SaveSnapshots::default();
+            match save_snapshots.find(id) {
+                Ok(it) => save_snapshots.remove(&it)?,
+                Err(err) => warn!(%err),
+            }
+        }
+        fs::remove_dir_all(&snapshot.path)?;
+
+        Ok(())
+    }
+    /// Removes the snapshots that are past the policy's limits.
+    pub fn prune(&self, policy: &SafetySnapshotPolicy) -> Result<()> {
+        let now = chrono::Local::now().naive_local();
+        let max_age = TimeDelta::days(policy.max_age_days.into());
+
+        let mut removed_save = false;
+        for (i, snapshot) in self.list()?.iter().enumerate() {
+            let is_too_old = policy.max_age_days > 0 && now - snapshot.time > max_age;
+            if i >= policy.max_count || is_too_old {
+                self.remove(snapshot)?;
+                removed_save |= snapshot.record.save_snapshot.is_some();
+            }
+        }
+        if removed_save {
+            SaveSnapshots::default().gc()?;
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,50 @@
+use std::{path::Path, time::Duration};
+
+use color_eyre::eyre::Result;
+use notify_debouncer_mini::{
+    new_debouncer, notify::RecommendedWatcher, notify::RecursiveMode, DebounceEventResult,
+    Debouncer,
+};
+use tracing::{debug, warn};
+
+/// The game writes its mod list in a few steps, events within this window are treated as one change.
+const DEBOUNCE_TIMEOUT: Duration = Duration::from_millis(500);
+
+/// Watches the game's `mod_config.xml` for changes, stops watching once dropped.
+#[derive(Debug)]
+pub struct ModConfigWatcher {
+    _debouncer: Debouncer<RecommendedWatcher>,
+}
+
+impl ModConfigWatcher {
+    /// `on_change` is called from the watcher's own thread.
+    pub fn new(
+        noita_save_dir: impl AsRef<Path>,
+        mut on_change: impl FnMut() + Send + 'static,
+    ) -> Result<Self> {
+        let save_dir = noita_save_dir.as_ref().to_owned();
+        let path = save_dir.join("mod_config.xml");
+
+        let mut debouncer =
+            new_debouncer(
+                DEBOUNCE_TIMEOUT,
+                move |res: DebounceEventResult| match res {
+                    Ok(events) => {
+                        if events.iter().any(|it| it.path == path) {
+                            debug!(?path, "mod_config.xml changed");
+                            on_change();
+                        }
+                    }
+                    Err(err) => warn!(%err, "Failed to watch mod_config.xml"),
+                },
+            )?;
+        // The file is replaced rather than written to in place, which would drop a watch on the file itself
+        debouncer
+            .watcher()
+            .watch(&save_dir, RecursiveMode::NonRecursive)?;
+
+        Ok(Self {
+            _debouncer: debouncer,
+        })
+    }
+}
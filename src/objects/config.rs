@@ -13,6 +13,19 @@ impl_deref_for_newtype!(ModProfiles, noita::ModProfiles);
 pub struct NoitaPath(pub noita::NoitaPath);
 impl_deref_for_newtype!(NoitaPath, noita::NoitaPath);
 
+#[derive(Debug, Clone, Default, glib::Boxed)]
+#[boxed_type(name = "NoitadSafetySnapshotPolicyBoxed")]
+pub struct SafetySnapshotPolicy(pub noita::switch_snapshots::SafetySnapshotPolicy);
+impl_deref_for_newtype!(
+    SafetySnapshotPolicy,
+    noita::switch_snapshots::SafetySnapshotPolicy
+);
+
+#[derive(Debug, Clone, Default, glib::Boxed)]
+#[boxed_type(name = "NoitadLinkedModsBoxed")]
+pub struct LinkedMods(pub noita::dev_links::LinkedMods);
+impl_deref_for_newtype!(LinkedMods, noita::dev_links::LinkedMods);
+
 mod imp {
     use std::cell::RefCell;
 
@@ -31,6 +44,10 @@ mod imp {
         pub active_profile: RefCell<Option<String>>,
         #[property(get, set)]
         pub active_profile_sync: RefCell<bool>,
+        #[property(get, set)]
+        pub safety_snapshots: RefCell<SafetySnapshotPolicy>,
+        #[property(get, set)]
+        pub linked_mods: RefCell<LinkedMods>,
     }
 
     #[glib::object_subclass]
@@ -58,6 +75,11 @@ impl ConfigObject {
             .property("profiles", ModProfiles(config.profiles))
             .property("active_profile", config.active_profile)
             .property("active_profile_sync", config.active_profile_sync)
+            .property(
+                "safety_snapshots",
+                SafetySnapshotPolicy(config.safety_snapshots),
+            )
+            .property("linked_mods", LinkedMods(config.linked_mods))
             .build()
     }
 
@@ -67,10 +89,15 @@ impl ConfigObject {
             ("profiles", &ModProfiles(config.profiles).to_value()),
             ("active_profile", &config.active_profile),
             ("active_profile_sync", &config.active_profile_sync),
+            (
+                "safety_snapshots",
+                &SafetySnapshotPolicy(config.safety_snapshots).to_value(),
+            ),
+            ("linked_mods", &LinkedMods(config.linked_mods).to_value()),
         ]);
     }
 
-    pub fn into_simple_config(&self) -> config::Config {
+    pub fn to_simple_config(&self) -> config::Config {
         self.clone().into()
     }
 }
@@ -81,13 +108,15 @@ impl Default for ConfigObject {
     }
 }
 
-impl Into<config::Config> for ConfigObject {
-    fn into(self) -> config::Config {
+impl From<ConfigObject> for config::Config {
+    fn from(value: ConfigObject) -> Self {
         config::Config {
-            noita_path: self.noita_path().to_owned().0,
-            profiles: self.profiles().to_owned().0,
-            active_profile: self.active_profile().to_owned(),
-            active_profile_sync: self.active_profile_sync().to_owned(),
+            noita_path: value.noita_path().to_owned().0,
+            profiles: value.profiles().to_owned().0,
+            active_profile: value.active_profile().to_owned(),
+            active_profile_sync: value.active_profile_sync().to_owned(),
+            safety_snapshots: value.safety_snapshots().to_owned().0,
+            linked_mods: value.linked_mods().to_owned().0,
         }
     }
 }
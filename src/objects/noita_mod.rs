@@ -1,7 +1,15 @@
 use adw::prelude::*;
 use adw::subclass::prelude::*;
 use gtk::glib;
-use noitad_lib::{impl_deref_for_newtype, noita};
+use itertools::Itertools;
+use noitad_lib::{
+    impl_deref_for_newtype,
+    noita::{
+        self,
+        lua_audit::{ModAudit, Risk},
+        mod_catalog::ModInfo,
+    },
+};
 
 #[derive(Debug, Clone, Default, glib::Boxed)]
 #[boxed_type(name = "NoitadModsBoxed")]
@@ -21,6 +29,26 @@ mod imp {
         #[property(get, set, name = "enabled", type = bool, member = enabled)]
         #[property(get, set, name = "name", type = String, member = name)]
         pub inner: RefCell<Mod>,
+        /// Display name from the mod's `mod.xml`
+        #[property(get, set)]
+        pub title: RefCell<String>,
+        #[property(get, set)]
+        pub description: RefCell<String>,
+        /// Whether the mod requests unrestricted API access in its `mod.xml`
+        #[property(get, set)]
+        pub unrestricted: RefCell<bool>,
+        /// High risk calls found in the mod's scripts, one per line
+        #[property(get, set)]
+        pub high_risk_findings: RefCell<String>,
+        /// Whether the mod's folder is gone, e.g. after unsubscribing from it
+        #[property(get, set)]
+        pub missing: RefCell<bool>,
+        /// Whether the mod's files changed since its profile was last saved
+        #[property(get, set)]
+        pub changed: RefCell<bool>,
+        /// Directory a mod under development is linked from, empty for other mods
+        #[property(get, set)]
+        pub linked_from: RefCell<String>,
     }
 
     #[glib::object_subclass]
@@ -42,16 +70,43 @@ glib::wrapper! {
 }
 
 impl ModObject {
-    pub fn new(mod_: noita::mod_config::Mod) -> Self {
-        let obj: Self = glib::Object::builder().build();
+    pub fn new(mod_: noita::mod_config::Mod, info: Option<&ModInfo>) -> Self {
+        let obj: Self = glib::Object::builder()
+            .property(
+                "title",
+                info.map(|it| it.title.as_str()).unwrap_or(&mod_.name),
+            )
+            .property(
+                "description",
+                info.map(|it| it.description.as_str()).unwrap_or_default(),
+            )
+            .property(
+                "unrestricted",
+                info.is_some_and(|it| it.request_no_api_restrictions),
+            )
+            .build();
         *obj.imp().inner.borrow_mut() = Mod(mod_);
 
         obj
     }
 
+    pub fn set_audit(&self, audit: &ModAudit) {
+        self.set_high_risk_findings(
+            audit
+                .findings
+                .iter()
+                .filter(|it| it.risk == Risk::High)
+                .join("\n"),
+        );
+    }
+
     pub fn is_local(&self) -> bool {
         self.imp().inner.borrow().is_local()
     }
+
+    pub fn id(&self) -> noita::mod_config::ModId {
+        self.imp().inner.borrow().id()
+    }
 }
 
 impl Default for ModObject {
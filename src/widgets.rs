@@ -1 +1,4 @@
+pub mod bisect;
+pub mod diagnostics;
 pub mod game_path_pref;
+pub mod save_snapshots;
@@ -0,0 +1,187 @@
+use std::path::PathBuf;
+
+use adw::prelude::*;
+use adw::subclass::prelude::*;
+use gtk::glib::{self, clone};
+use noitad_lib::noita::{
+    bisect::{Bisect, Verdict},
+    mod_catalog::ModCatalog,
+    mod_config::{ModId, Mods},
+};
+use tracing::error;
+
+mod imp {
+    use std::cell::RefCell;
+
+    use super::*;
+
+    #[derive(Debug, Default, gtk::CompositeTemplate)]
+    #[template(resource = "/io/github/nozwock/noitad/ui/bisect.ui")]
+    pub struct BisectDialog {
+        #[template_child]
+        pub toast_overlay: TemplateChild<adw::ToastOverlay>,
+        #[template_child]
+        pub button_stop: TemplateChild<gtk::Button>,
+        #[template_child]
+        pub stack: TemplateChild<gtk::Stack>,
+        #[template_child]
+        pub status_step: TemplateChild<adw::StatusPage>,
+        #[template_child]
+        pub step_mods_list: TemplateChild<gtk::ListBox>,
+        #[template_child]
+        pub culprits_list: TemplateChild<gtk::ListBox>,
+        #[template_child]
+        pub step_actions: TemplateChild<gtk::Box>,
+        #[template_child]
+        pub button_good: TemplateChild<gtk::Button>,
+        #[template_child]
+        pub button_bad: TemplateChild<gtk::Button>,
+
+        pub bisect: RefCell<Bisect>,
+        /// Full mod list of the profile being bisected
+        pub mod_list: RefCell<Mods>,
+        pub save_dir: RefCell<PathBuf>,
+        pub catalog: RefCell<ModCatalog>,
+    }
+
+    #[glib::object_subclass]
+    impl ObjectSubclass for BisectDialog {
+        const NAME: &'static str = "BisectDialog";
+        type Type = super::BisectDialog;
+        type ParentType = adw::Dialog;
+
+        fn class_init(klass: &mut Self::Class) {
+            klass.bind_template();
+        }
+
+        // You must call `Widget`'s `init_template()` within `instance_init()`.
+        fn instance_init(obj: &glib::subclass::InitializingObject<Self>) {
+            obj.init_template();
+        }
+    }
+
+    impl ObjectImpl for BisectDialog {}
+    impl WidgetImpl for BisectDialog {}
+    impl AdwDialogImpl for BisectDialog {}
+}
+
+glib::wrapper! {
+    pub struct BisectDialog(ObjectSubclass<imp::BisectDialog>)
+        @extends adw::Dialog, gtk::Widget,
+        @implements gtk::Accessible, gtk::Buildable, gtk::ConstraintTarget;
+}
+
+impl BisectDialog {
+    /// Walks the user through the steps of a bisect that's already been started.
+    pub fn new(bisect: Bisect, mod_list: Mods, save_dir: PathBuf, catalog: ModCatalog) -> Self {
+        let obj: Self = glib::Object::builder().build();
+        let imp = obj.imp();
+        imp.bisect.replace(bisect);
+        imp.mod_list.replace(mod_list);
+        imp.save_dir.replace(save_dir);
+        imp.catalog.replace(catalog);
+        obj.setup_ui();
+
+        obj
+    }
+
+    fn setup_ui(&self) {
+        let imp = self.imp();
+
+        imp.button_good.connect_clicked(clone!(
+            #[weak(rename_to = obj)]
+            self,
+            move |_| obj.answer(Verdict::Good)
+        ));
+        imp.button_bad.connect_clicked(clone!(
+            #[weak(rename_to = obj)]
+            self,
+            move |_| obj.answer(Verdict::Bad)
+        ));
+        imp.button_stop.connect_clicked(clone!(
+            #[weak(rename_to = obj)]
+            self,
+            move |_| {
+                let imp = obj.imp();
+                let res = imp
+                    .bisect
+                    .borrow()
+                    .reset(&imp.mod_list.borrow(), imp.save_dir.borrow().as_path());
+                match res {
+                    Ok(_) => _ = obj.close(),
+                    Err(err) => obj.toast_error(err),
+                }
+            }
+        ));
+
+        self.show_step();
+    }
+
+    fn answer(&self, verdict: Verdict) {
+        let imp = self.imp();
+        let res = imp.bisect.borrow_mut().answer(
+            verdict,
+            &imp.mod_list.borrow(),
+            imp.save_dir.borrow().as_path(),
+        );
+        match res {
+            Ok(Some(culprits)) => self.show_result(&culprits),
+            Ok(None) => self.show_step(),
+            Err(err) => self.toast_error(err),
+        }
+    }
+
+    fn show_step(&self) {
+        let imp = self.imp();
+        let bisect = imp.bisect.borrow();
+        let mods = bisect.step_mods();
+
+        imp.status_step.set_title(&format!(
+            "{} Mods Enabled, About {} Steps Left",
+            mods.len(),
+            bisect.steps_left()
+        ));
+        self.fill_mods_list(&imp.step_mods_list, &mods, bisect.culprits());
+    }
+
+    fn show_result(&self, culprits: &[ModId]) {
+        let imp = self.imp();
+        imp.button_stop.set_visible(false);
+        imp.step_actions.set_visible(false);
+        self.fill_mods_list(&imp.culprits_list, culprits, &[]);
+        imp.stack.set_visible_child_name("result");
+    }
+
+    /// Lists the mods, marking the ones already known to be part of the problem.
+    fn fill_mods_list(&self, list: &gtk::ListBox, mods: &[ModId], culprits: &[ModId]) {
+        let catalog = self.imp().catalog.borrow();
+        list.remove_all();
+        for id in mods {
+            let title = catalog
+                .get(id)
+                .map(|it| it.title.clone())
+                .unwrap_or_else(|| id.name.clone());
+            let row = adw::ActionRow::builder()
+                .title(title)
+                .subtitle(id.to_string())
+                .use_markup(false)
+                .build();
+            if culprits.contains(id) {
+                let icon = gtk::Image::builder()
+                    .icon_name("dialog-warning-symbolic")
+                    .tooltip_text("Needed for the problem to show up")
+                    .css_classes(["warning"])
+                    .build();
+                row.add_suffix(&icon);
+            }
+            list.append(&row);
+        }
+    }
+
+    fn toast_error(&self, err: color_eyre::eyre::Report) {
+        error!(%err);
+        self.imp()
+            .toast_overlay
+            .add_toast(adw::Toast::builder().title(err.to_string()).build());
+    }
+}
@@ -0,0 +1,181 @@
+use std::path::PathBuf;
+
+use adw::prelude::*;
+use adw::subclass::prelude::*;
+use gtk::glib::{self, clone};
+use noitad_lib::noita::{
+    game_log::{GameLog, LogEntry, Severity},
+    mod_catalog::ModCatalog,
+    mod_config::Mods,
+};
+use tracing::error;
+
+mod imp {
+    use std::cell::RefCell;
+
+    use super::*;
+
+    #[derive(Debug, Default, gtk::CompositeTemplate)]
+    #[template(resource = "/io/github/nozwock/noitad/ui/diagnostics.ui")]
+    pub struct DiagnosticsDialog {
+        #[template_child]
+        pub button_refresh: TemplateChild<gtk::Button>,
+        #[template_child]
+        pub stack: TemplateChild<gtk::Stack>,
+        #[template_child]
+        pub status_empty: TemplateChild<adw::StatusPage>,
+        #[template_child]
+        pub report_list: TemplateChild<gtk::ListBox>,
+
+        pub game_root: RefCell<Option<PathBuf>>,
+        /// Mod list of the profile the log is matched against
+        pub mod_list: RefCell<Mods>,
+        pub catalog: RefCell<ModCatalog>,
+    }
+
+    #[glib::object_subclass]
+    impl ObjectSubclass for DiagnosticsDialog {
+        const NAME: &'static str = "DiagnosticsDialog";
+        type Type = super::DiagnosticsDialog;
+        type ParentType = adw::Dialog;
+
+        fn class_init(klass: &mut Self::Class) {
+            klass.bind_template();
+        }
+
+        // You must call `Widget`'s `init_template()` within `instance_init()`.
+        fn instance_init(obj: &glib::subclass::InitializingObject<Self>) {
+            obj.init_template();
+        }
+    }
+
+    impl ObjectImpl for DiagnosticsDialog {}
+    impl WidgetImpl for DiagnosticsDialog {}
+    impl AdwDialogImpl for DiagnosticsDialog {}
+}
+
+glib::wrapper! {
+    pub struct DiagnosticsDialog(ObjectSubclass<imp::DiagnosticsDialog>)
+        @extends adw::Dialog, gtk::Widget,
+        @implements gtk::Accessible, gtk::Buildable, gtk::ConstraintTarget;
+}
+
+impl DiagnosticsDialog {
+    pub fn new(game_root: Option<PathBuf>, mod_list: Mods, catalog: ModCatalog) -> Self {
+        let obj: Self = glib::Object::builder().build();
+        let imp = obj.imp();
+        imp.game_root.replace(game_root);
+        imp.mod_list.replace(mod_list);
+        imp.catalog.replace(catalog);
+
+        imp.button_refresh.connect_clicked(clone!(
+            #[weak]
+            obj,
+            move |_| obj.refresh()
+        ));
+        obj.refresh();
+
+        obj
+    }
+
+    fn refresh(&self) {
+        let imp = self.imp();
+        let report_list = imp.report_list.get();
+        report_list.remove_all();
+
+        let Some(game_root) = imp.game_root.borrow().clone() else {
+            self.show_empty("Couldn't find the game's directory");
+            return;
+        };
+        let log = match GameLog::from_game_root(&game_root) {
+            Ok(log) => log,
+            Err(err) => {
+                error!(%err, "Couldn't read the game's log");
+                self.show_empty("The game hasn't left a log behind yet");
+                return;
+            }
+        };
+        let summary = log.summarize(&imp.mod_list.borrow());
+        if summary.mods.is_empty() && summary.unattributed.is_empty() {
+            self.show_empty("The game's log has no errors or warnings");
+            return;
+        }
+
+        let catalog = imp.catalog.borrow();
+        for report in summary.mods.iter() {
+            let title = catalog
+                .get(&report.id)
+                .map(|it| it.title.clone())
+                .unwrap_or_else(|| report.id.name.clone());
+            let row = report_row(
+                &title,
+                format!(
+                    "{} errors, {} warnings",
+                    report.count(Severity::Error),
+                    report.count(Severity::Warning)
+                ),
+                &report.entries,
+            );
+            report_list.append(&row);
+        }
+        if !summary.unattributed.is_empty() {
+            let row = report_row(
+                "Not Tied to a Mod",
+                format!("{} entries", summary.unattributed.len()),
+                &summary.unattributed,
+            );
+            report_list.append(&row);
+        }
+        imp.stack.set_visible_child_name("report");
+    }
+
+    fn show_empty(&self, description: &str) {
+        let imp = self.imp();
+        imp.status_empty.set_description(Some(description));
+        imp.stack.set_visible_child_name("empty");
+    }
+}
+
+fn report_row(title: &str, subtitle: String, entries: &[LogEntry]) -> adw::ExpanderRow {
+    let row = adw::ExpanderRow::builder()
+        .title(title)
+        .subtitle(subtitle)
+        .use_markup(false)
+        .build();
+    for entry in entries {
+        let label = gtk::Label::builder()
+            .label(&entry.message)
+            .wrap(true)
+            .wrap_mode(gtk::pango::WrapMode::WordChar)
+            .xalign(0.)
+            .selectable(true)
+            .hexpand(true)
+            .css_classes([
+                "monospace",
+                match entry.severity {
+                    Severity::Error => "error",
+                    Severity::Warning => "warning",
+                },
+            ])
+            .build();
+        let line = gtk::Box::builder()
+            .spacing(12)
+            .margin_top(6)
+            .margin_bottom(6)
+            .margin_start(12)
+            .margin_end(12)
+            .build();
+        line.append(&label);
+        if entry.count > 1 {
+            let count = gtk::Label::builder()
+                .label(format!("×{}", entry.count))
+                .valign(gtk::Align::Start)
+                .css_classes(["dim-label", "numeric"])
+                .build();
+            line.append(&count);
+        }
+        row.add_row(&line);
+    }
+
+    row
+}
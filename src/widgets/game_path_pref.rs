@@ -172,8 +172,7 @@ impl GamePathPreference {
                 callback(
                     file.inspect_err(|err| error!(%err))
                         .ok()
-                        .map(|it| it.path())
-                        .flatten(),
+                        .and_then(|it| it.path()),
                 );
             },
         );
@@ -0,0 +1,302 @@
+use std::path::PathBuf;
+
+use adw::prelude::*;
+use adw::subclass::prelude::*;
+use color_eyre::eyre::{eyre, Result};
+use gtk::{
+    gio,
+    glib::{self, clone},
+};
+use noitad_lib::noita::save_snapshots::{format_size, SaveSnapshot, SaveSnapshots};
+use tracing::error;
+
+mod imp {
+    use std::{cell::RefCell, path::PathBuf};
+
+    use super::*;
+
+    #[derive(Debug, Default, gtk::CompositeTemplate)]
+    #[template(resource = "/io/github/nozwock/noitad/ui/save_snapshots.ui")]
+    pub struct SaveSnapshotsDialog {
+        #[template_child]
+        pub toast_overlay: TemplateChild<adw::ToastOverlay>,
+        #[template_child]
+        pub button_backup: TemplateChild<gtk::Button>,
+        #[template_child]
+        pub stack: TemplateChild<gtk::Stack>,
+        #[template_child]
+        pub status_empty: TemplateChild<adw::StatusPage>,
+        #[template_child]
+        pub snapshots_list: TemplateChild<gtk::ListBox>,
+
+        pub save_dir: RefCell<Option<PathBuf>>,
+        pub snapshots: SaveSnapshots,
+    }
+
+    #[glib::object_subclass]
+    impl ObjectSubclass for SaveSnapshotsDialog {
+        const NAME: &'static str = "SaveSnapshotsDialog";
+        type Type = super::SaveSnapshotsDialog;
+        type ParentType = adw::Dialog;
+
+        fn class_init(klass: &mut Self::Class) {
+            klass.bind_template();
+        }
+
+        // You must call `Widget`'s `init_template()` within `instance_init()`.
+        fn instance_init(obj: &glib::subclass::InitializingObject<Self>) {
+            obj.init_template();
+        }
+    }
+
+    impl ObjectImpl for SaveSnapshotsDialog {}
+    impl WidgetImpl for SaveSnapshotsDialog {}
+    impl AdwDialogImpl for SaveSnapshotsDialog {}
+}
+
+glib::wrapper! {
+    pub struct SaveSnapshotsDialog(ObjectSubclass<imp::SaveSnapshotsDialog>)
+        @extends adw::Dialog, gtk::Widget,
+        @implements gtk::Accessible, gtk::Buildable, gtk::ConstraintTarget;
+}
+
+impl SaveSnapshotsDialog {
+    pub fn new(save_dir: Option<PathBuf>) -> Self {
+        let obj: Self = glib::Object::builder().build();
+        obj.imp().save_dir.replace(save_dir);
+        obj.setup_ui();
+
+        obj
+    }
+
+    fn setup_ui(&self) {
+        let imp = self.imp();
+
+        let button_backup = imp.button_backup.get();
+        if imp.save_dir.borrow().is_none() {
+            button_backup.set_sensitive(false);
+            imp.status_empty
+                .set_description(Some("Couldn't find the game's save directory"));
+        }
+        button_backup.connect_clicked(clone!(
+            #[weak(rename_to = obj)]
+            self,
+            move |_| obj.present_backup_dialog()
+        ));
+
+        self.refresh();
+    }
+
+    fn refresh(&self) {
+        let imp = self.imp();
+        let snapshots_list = imp.snapshots_list.get();
+        snapshots_list.remove_all();
+
+        let snapshots = imp
+            .snapshots
+            .list()
+            .inspect_err(|err| error!(%err))
+            .unwrap_or_default();
+        imp.stack
+            .set_visible_child_name(match snapshots.is_empty() {
+                true => "empty",
+                false => "snapshots",
+            });
+
+        for snapshot in snapshots {
+            let row = adw::ActionRow::builder()
+                .title(
+                    snapshot
+                        .meta
+                        .label
+                        .clone()
+                        .unwrap_or_else(|| "Untitled".to_owned()),
+                )
+                .subtitle(format!(
+                    "{} — {}",
+                    snapshot.time.format("%Y-%m-%d %H:%M:%S"),
+                    format_size(snapshot.size())
+                ))
+                .use_markup(false)
+                .build();
+
+            let button_restore = gtk::Button::builder()
+                .icon_name("document-revert-symbolic")
+                .tooltip_text("Restore")
+                .valign(gtk::Align::Center)
+                .sensitive(imp.save_dir.borrow().is_some())
+                .css_classes(["flat"])
+                .build();
+            button_restore.connect_clicked(clone!(
+                #[weak(rename_to = obj)]
+                self,
+                #[strong]
+                snapshot,
+                move |_| obj.present_restore_dialog(snapshot.clone())
+            ));
+            let button_delete = gtk::Button::builder()
+                .icon_name("user-trash-symbolic")
+                .tooltip_text("Delete")
+                .valign(gtk::Align::Center)
+                .css_classes(["flat"])
+                .build();
+            button_delete.connect_clicked(clone!(
+                #[weak(rename_to = obj)]
+                self,
+                #[strong]
+                snapshot,
+                move |_| obj.present_delete_dialog(snapshot.clone())
+            ));
+
+            row.add_suffix(&button_restore);
+            row.add_suffix(&button_delete);
+            snapshots_list.append(&row);
+        }
+    }
+
+    fn toast(&self, title: impl AsRef<str>) {
+        self.imp()
+            .toast_overlay
+            .add_toast(adw::Toast::builder().title(title.as_ref()).build());
+    }
+
+    fn present_backup_dialog(&self) {
+        let Some(save_dir) = self.imp().save_dir.borrow().clone() else {
+            return;
+        };
+
+        let dialog = adw::AlertDialog::builder()
+            .close_response("cancel")
+            .heading("Back Up Save")
+            .build();
+        dialog.add_responses(&[("cancel", "Cancel"), ("backup", "Back Up")]);
+        dialog.set_response_appearance("backup", adw::ResponseAppearance::Suggested);
+
+        let list_box = gtk::ListBox::builder()
+            .selection_mode(gtk::SelectionMode::None)
+            .margin_top(12)
+            .build();
+        list_box.add_css_class("boxed-list");
+        let entry_row = adw::EntryRow::builder().title("Label (Optional)").build();
+        list_box.append(&entry_row);
+        dialog.set_extra_child(Some(&list_box));
+
+        dialog.choose(
+            self,
+            None::<&gio::Cancellable>,
+            clone!(
+                #[weak(rename_to = obj)]
+                self,
+                move |resp| {
+                    if resp.as_str() != "backup" {
+                        return;
+                    }
+                    let label =
+                        Some(entry_row.text().trim().to_owned()).filter(|it| !it.is_empty());
+                    let snapshots = obj.imp().snapshots.clone();
+                    obj.run_in_background(
+                        move || snapshots.create(save_dir, label).map(|_| ()),
+                        "Backed up the save".to_owned(),
+                    );
+                }
+            ),
+        );
+    }
+
+    fn present_restore_dialog(&self, snapshot: SaveSnapshot) {
+        let Some(save_dir) = self.imp().save_dir.borrow().clone() else {
+            return;
+        };
+
+        let dialog = adw::AlertDialog::builder()
+            .close_response("cancel")
+            .heading("Restore Save?")
+            .body("The current save will be replaced, a backup of it is made first.")
+            .build();
+        dialog.add_responses(&[("cancel", "Cancel"), ("restore", "Restore")]);
+        dialog.set_response_appearance("restore", adw::ResponseAppearance::Destructive);
+
+        dialog.choose(
+            self,
+            None::<&gio::Cancellable>,
+            clone!(
+                #[weak(rename_to = obj)]
+                self,
+                move |resp| {
+                    if resp.as_str() != "restore" {
+                        return;
+                    }
+                    let snapshots = obj.imp().snapshots.clone();
+                    obj.run_in_background(
+                        move || snapshots.restore(&snapshot, save_dir).map(|_| ()),
+                        "Restored the save".to_owned(),
+                    );
+                }
+            ),
+        );
+    }
+
+    fn present_delete_dialog(&self, snapshot: SaveSnapshot) {
+        let dialog = adw::AlertDialog::builder()
+            .close_response("cancel")
+            .heading("Delete Save Backup?")
+            .body(snapshot.to_string())
+            .build();
+        dialog.add_responses(&[("cancel", "Cancel"), ("delete", "Delete")]);
+        dialog.set_response_appearance("delete", adw::ResponseAppearance::Destructive);
+
+        dialog.choose(
+            self,
+            None::<&gio::Cancellable>,
+            clone!(
+                #[weak(rename_to = obj)]
+                self,
+                move |resp| {
+                    if resp.as_str() != "delete" {
+                        return;
+                    }
+                    let snapshots = obj.imp().snapshots.clone();
+                    obj.run_in_background(
+                        move || {
+                            snapshots.remove(&snapshot)?;
+                            snapshots.gc().map(|_| ())
+                        },
+                        "Deleted the save backup".to_owned(),
+                    );
+                }
+            ),
+        );
+    }
+
+    /// Archiving a save can take a while, so it's kept off the main thread.
+    fn run_in_background(
+        &self,
+        task: impl FnOnce() -> Result<()> + Send + 'static,
+        success_msg: String,
+    ) {
+        let button_backup = self.imp().button_backup.get();
+        button_backup.set_sensitive(false);
+        self.imp().snapshots_list.set_sensitive(false);
+
+        glib::spawn_future_local(clone!(
+            #[weak(rename_to = obj)]
+            self,
+            async move {
+                let res = gio::spawn_blocking(task)
+                    .await
+                    .unwrap_or_else(|_| Err(eyre!("Background task panicked")));
+                match res {
+                    Ok(_) => obj.toast(success_msg),
+                    Err(err) => {
+                        error!(%err);
+                        obj.toast(err.to_string());
+                    }
+                }
+
+                obj.imp().button_backup.set_sensitive(true);
+                obj.imp().snapshots_list.set_sensitive(true);
+                obj.refresh();
+            }
+        ));
+    }
+}
@@ -1,27 +1,38 @@
 use std::borrow::BorrowMut;
 use std::cell::{Cell, RefMut};
 use std::collections::HashMap;
+use std::path::{Path, PathBuf};
 use std::rc::Rc;
 
 use adw::prelude::*;
 use adw::subclass::prelude::*;
-use color_eyre::eyre::Result;
+use color_eyre::eyre::{ContextCompat, Result};
 use gtk::gio::ListStore;
 use gtk::glib::clone;
-use gtk::{gio, glib, ListBox, SingleSelection, StringObject};
+use gtk::{gdk, gio, glib, ListBox, SingleSelection, StringObject};
 use itertools::Itertools;
 use noitad_lib::config::Config;
 use noitad_lib::defines::APP_CONFIG_PATH;
-use noitad_lib::noita::mod_config::Mods;
+use noitad_lib::noita::bisect::Bisect;
+use noitad_lib::noita::lua_audit::ModAudit;
+use noitad_lib::noita::mod_catalog::{ModCatalog, ModInfo};
+use noitad_lib::noita::mod_config::{Mod, ModId, Mods};
+use noitad_lib::noita::mod_install::install_archive;
+use noitad_lib::noita::orphans::InstalledMods;
+use noitad_lib::noita::process::NoitaProcess;
+use noitad_lib::noita::watcher::ModConfigWatcher;
 use noitad_lib::noita::{GamePath, NoitaPath};
 use tracing::{debug, error, info};
 
 use crate::application::NoitadApplication;
 use crate::config::{APP_ID, PROFILE};
 use crate::objects;
-use crate::objects::config::{ConfigObject, ModProfiles};
+use crate::objects::config::{ConfigObject, LinkedMods, ModProfiles};
 use crate::objects::noita_mod::ModObject;
+use crate::widgets::bisect::BisectDialog;
+use crate::widgets::diagnostics::DiagnosticsDialog;
 use crate::widgets::game_path_pref::GamePathPreference;
+use crate::widgets::save_snapshots::SaveSnapshotsDialog;
 
 mod imp {
     use std::{cell::RefCell, collections::HashMap, rc::Rc};
@@ -56,6 +67,8 @@ mod imp {
         pub mod_list: TemplateChild<gtk::ListBox>,
         #[template_child]
         pub button_save_mod_list: TemplateChild<gtk::Button>,
+        #[template_child]
+        pub banner_mod_config_drift: TemplateChild<adw::Banner>,
 
         #[default(gio::Settings::new(APP_ID))]
         pub settings: gio::Settings,
@@ -63,7 +76,11 @@ mod imp {
         pub is_initial_setup_done: Rc<RefCell<Option<bool>>>,
 
         pub mod_list_models: Rc<RefCell<HashMap<String, Vec<ModObject>>>>,
+        /// Installed mods, scanned lazily once a profile is first shown
+        pub mod_catalog: Rc<RefCell<Option<ModCatalog>>>,
         pub is_profile_modified: Rc<RefCell<HashMap<String, bool>>>,
+        /// Keeps the active profile in sync with the game, if enabled
+        pub mod_config_watcher: RefCell<Option<ModConfigWatcher>>,
     }
 
     #[glib::object_subclass]
@@ -117,7 +134,7 @@ mod imp {
                 // Initial setup was started but not completed, skip default serialization
                 Some(false) => {}
                 _ => {
-                    _ = dbg!(dbg!(self.config.into_simple_config()).store());
+                    _ = dbg!(dbg!(self.config.to_simple_config()).store());
                 }
             }
 
@@ -176,7 +193,20 @@ impl NoitadApplicationWindow {
             })
             .build();
 
-        self.add_action_entries([action_profile_new]);
+        let action_save_backups = gio::ActionEntry::builder("save-backups")
+            .activate(|window: &Self, _, _| {
+                SaveSnapshotsDialog::new(window.imp().config.noita_path().save_dir())
+                    .present(Some(window));
+            })
+            .build();
+
+        let action_diagnostics = gio::ActionEntry::builder("diagnostics")
+            .activate(|window: &Self, _, _| {
+                window.present_diagnostics_dialog();
+            })
+            .build();
+
+        self.add_action_entries([action_profile_new, action_save_backups, action_diagnostics]);
     }
 
     fn setup_ui(&self) {
@@ -200,7 +230,7 @@ impl NoitadApplicationWindow {
         let cfg = &imp.config;
         let mod_list_model = gio::ListStore::new::<ModObject>();
 
-        if cfg.profiles().len() != 0 {
+        if !cfg.profiles().is_empty() {
             self.setup_profile_sidebar(&mod_list_model);
         } else {
             imp.sidebar_split.get().set_collapsed(true);
@@ -226,6 +256,8 @@ impl NoitadApplicationWindow {
         }
 
         self.setup_mod_list(&mod_list_model);
+        self.setup_mod_config_watcher();
+        self.setup_archive_drop();
     }
 
     fn setup_welcome_page(&self) {
@@ -338,6 +370,7 @@ impl NoitadApplicationWindow {
 
         let profiles_list = imp.profiles_list.get();
         let mod_list_models = imp.mod_list_models.clone();
+        let mod_catalog = imp.mod_catalog.clone();
 
         fn sync_profiles_model(profiles: &ModProfiles, model: &ListStore) {
             model.retain(|s| {
@@ -351,7 +384,7 @@ impl NoitadApplicationWindow {
                 let mut i = 0;
                 while let Some(obj) = model.item(i) {
                     item_store.push(obj.downcast::<StringObject>().unwrap());
-                    i = i + 1;
+                    i += 1;
                 }
 
                 profiles.keys().for_each(|s| {
@@ -372,7 +405,7 @@ impl NoitadApplicationWindow {
                 .enumerate()
                 .for_each(|(i, s)| {
                     if model.find(s).is_none() {
-                        model.splice(i as u32, 0, &[s.clone()]);
+                        model.splice(i as u32, 0, std::slice::from_ref(s));
                     }
                 });
         }
@@ -393,6 +426,8 @@ impl NoitadApplicationWindow {
         profiles_list.bind_model(
             Some(&selection_model),
             clone!(
+                #[weak(rename_to = window)]
+                self,
                 #[weak]
                 cfg,
                 #[upgrade_or_panic]
@@ -417,14 +452,14 @@ impl NoitadApplicationWindow {
                     // Others say that just there being an invalid action name or maybe just None(?), should make
                     // the menu item button insensitive but ofcourse it DOESN'T WORK!
                     //
-                    // So I've just decided for now to disable the whole popover_button altogther for the default profile row.
-                    // That works in our case...
+                    // So I've just decided for now to swap out the menu of the default profile row for one
+                    // without those items. That works in our case...
                     //
                     // So it turns out, this ActionEntry is some weird API to create Actions quickly
                     // and so set_enabled(), etc methods don't exist for it and instead we have to get
                     // action from the SimpleActionGroup via the lookup_action() method
                     //
-                    // Since I've settled for disabling the popover button instead, but incase I ever want to disable the action
+                    // Since I've settled for swapping the menu instead, but incase I ever want to disable the action
                     // then here it is...
                     // ```
                     // action_group
@@ -435,20 +470,11 @@ impl NoitadApplicationWindow {
                     //     .set_enabled(false);
                     // ```
 
-                    let menu_model = gio::Menu::new();
-                    menu_model.append_item(&gio::MenuItem::new(
-                        Some("Set as Default"),
-                        Some("profile-row.set-default"),
-                    ));
-                    menu_model.append_item(&gio::MenuItem::new(
-                        Some("Remove Profile"),
-                        Some("profile-row.remove"),
-                    ));
                     let popover_button = gtk::MenuButton::builder()
                         .valign(gtk::Align::Center)
                         .icon_name("view-more-symbolic")
                         .css_classes(["flat"])
-                        .menu_model(&menu_model)
+                        .menu_model(&profile_row_menu(false))
                         .build();
 
                     let action_group = gio::SimpleActionGroup::new();
@@ -461,9 +487,33 @@ impl NoitadApplicationWindow {
                             #[weak]
                             profile,
                             #[weak]
-                            cfg,
+                            window,
                             move |_, _, _| {
-                                cfg.set_active_profile(Some(profile.string()));
+                                window.switch_profile(profile.string().into());
+                            }
+                        ))
+                        .build();
+                    let isolated_save = cfg
+                        .profiles()
+                        .get(profile.string().as_str())
+                        .is_some_and(|it| it.isolated_save);
+                    let separate_save = gio::ActionEntry::builder("separate-save")
+                        .state(isolated_save.to_variant())
+                        .activate(clone!(
+                            #[weak]
+                            profile,
+                            #[weak]
+                            window,
+                            move |_, action, _| {
+                                let isolated = !action
+                                    .state()
+                                    .and_then(|it| it.get::<bool>())
+                                    .unwrap_or_default();
+                                match window.set_isolated_save(profile.string().as_str(), isolated)
+                                {
+                                    Ok(_) => action.set_state(&isolated.to_variant()),
+                                    Err(err) => error!(%err, "Couldn't change the profile's save"),
+                                }
                             }
                         ))
                         .build();
@@ -472,17 +522,67 @@ impl NoitadApplicationWindow {
                             #[weak]
                             profile,
                             #[weak]
-                            cfg,
+                            window,
                             move |_, _, _| {
-                                let mut profiles = cfg.profiles();
-                                _ = profiles
+                                _ = window
                                     .remove_profile(profile.string().as_str())
                                     .inspect_err(|err| error!(%err));
-                                cfg.set_profiles(profiles);
                             }
                         ))
                         .build();
-                    action_group.add_action_entries([set_default, remove_profile]);
+                    let rename_profile = gio::ActionEntry::builder("rename")
+                        .activate(clone!(
+                            #[weak]
+                            profile,
+                            #[weak]
+                            window,
+                            move |_, _, _| {
+                                window.present_profile_rename_dialog(profile.string().into());
+                            }
+                        ))
+                        .build();
+                    let compare_profile = gio::ActionEntry::builder("compare")
+                        .activate(clone!(
+                            #[weak]
+                            profile,
+                            #[weak]
+                            window,
+                            move |_, _, _| {
+                                window.present_profile_compare_dialog(profile.string().into());
+                            }
+                        ))
+                        .build();
+                    let bisect_profile = gio::ActionEntry::builder("bisect")
+                        .activate(clone!(
+                            #[weak]
+                            profile,
+                            #[weak]
+                            window,
+                            move |_, _, _| {
+                                window.present_bisect_dialog(profile.string().into());
+                            }
+                        ))
+                        .build();
+                    let duplicate_profile = gio::ActionEntry::builder("duplicate")
+                        .activate(clone!(
+                            #[weak]
+                            profile,
+                            #[weak]
+                            window,
+                            move |_, _, _| {
+                                window.present_profile_duplicate_dialog(profile.string().into());
+                            }
+                        ))
+                        .build();
+                    action_group.add_action_entries([
+                        set_default,
+                        separate_save,
+                        rename_profile,
+                        duplicate_profile,
+                        compare_profile,
+                        bisect_profile,
+                        remove_profile,
+                    ]);
                     row.insert_action_group("profile-row", Some(&action_group));
 
                     row.add_suffix(&default_profile);
@@ -495,6 +595,8 @@ impl NoitadApplicationWindow {
 
         let mod_list_page = imp.mod_list_page.get();
         profiles_list.connect_row_selected(clone!(
+            #[weak(rename_to = window)]
+            self,
             #[weak]
             cfg,
             #[weak]
@@ -503,15 +605,16 @@ impl NoitadApplicationWindow {
             mod_list_models,
             #[weak]
             mod_list_page,
+            #[weak]
+            mod_catalog,
             move |_obj, row| {
-                let active_profile =
-                    match row.map(|it| it.downcast_ref::<adw::ActionRow>()).flatten() {
-                        Some(it) => it.title(),
-                        None => {
-                            // When deleting a selected profile
-                            return;
-                        }
-                    };
+                let active_profile = match row.and_then(|it| it.downcast_ref::<adw::ActionRow>()) {
+                    Some(it) => it.title(),
+                    None => {
+                        // When deleting a selected profile
+                        return;
+                    }
+                };
                 mod_list_page.set_title(&format!("Profile — {}", active_profile.as_str()));
 
                 // Update mod_list
@@ -532,13 +635,17 @@ impl NoitadApplicationWindow {
                         .borrow()
                         .get(active_profile.as_str())
                         .unwrap()
-                        .into_iter()
-                        .map(|it| it.clone())
-                        .collect_vec()
+                        .to_vec()
                 } else {
+                    let mut mod_catalog = mod_catalog.as_ref().borrow_mut();
+                    let catalog =
+                        mod_catalog.get_or_insert_with(|| ModCatalog::scan(&cfg.noita_path()));
                     let mod_objs = Self::get_profile_mod_objs(
                         &cfg.profiles(),
                         &active_profile,
+                        catalog,
+                        &InstalledMods::new(&cfg.noita_path()),
+                        &cfg.linked_mods(),
                         mod_list_models.as_ref().borrow_mut(),
                     );
 
@@ -548,6 +655,8 @@ impl NoitadApplicationWindow {
                 mod_list_model.remove_all();
                 // todo: This also needs to be when the first profile is created
                 mod_list_model.extend_from_slice(&mod_objs);
+
+                window.update_drift_banner();
             }
         ));
 
@@ -614,13 +723,9 @@ impl NoitadApplicationWindow {
                         .find_map(|child| child.downcast::<gtk::MenuButton>().ok())
                         .unwrap();
 
-                    if cfg.active_profile().unwrap() == row.title().as_str() {
-                        image.set_visible(true);
-                        popover_button.set_sensitive(false);
-                    } else {
-                        image.set_visible(false);
-                        popover_button.set_sensitive(true);
-                    }
+                    let is_default = cfg.active_profile().unwrap() == row.title().as_str();
+                    image.set_visible(is_default);
+                    popover_button.set_menu_model(Some(&profile_row_menu(is_default)));
                 }
             }
         ));
@@ -639,16 +744,121 @@ impl NoitadApplicationWindow {
 
         let button_save_mod_list = imp.button_save_mod_list.get();
         let is_profile_modified = imp.is_profile_modified.clone();
+        let mod_list_models = imp.mod_list_models.clone();
+        let model = mod_list_model.clone();
 
         mod_list.bind_model(Some(mod_list_model), move |obj| {
             let item = obj.downcast_ref::<ModObject>().unwrap();
-            let row = adw::SwitchRow::builder().title(item.name()).build();
+            let row = adw::SwitchRow::builder()
+                .title(item.title())
+                .subtitle(item.description())
+                .subtitle_lines(2)
+                .use_markup(false)
+                .build();
 
             item.bind_property("enabled", &row, "active")
                 .bidirectional()
                 .sync_create()
                 .build();
 
+            if item.unrestricted() {
+                row.add_suffix(
+                    &gtk::Image::builder()
+                        .icon_name("dialog-warning-symbolic")
+                        .tooltip_text("Requests unrestricted API access, which lets it run any code on your computer")
+                        .css_classes(["warning"])
+                        .build(),
+                );
+            }
+
+            if item.missing() {
+                row.add_css_class("dim-label");
+                row.add_suffix(
+                    &gtk::Image::builder()
+                        .icon_name("action-unavailable-symbolic")
+                        .tooltip_text("Not installed anymore")
+                        .build(),
+                );
+            }
+
+            // Filled in once the mod's been hashed in the background
+            let changed_badge = gtk::Image::builder()
+                .icon_name("software-update-available-symbolic")
+                .tooltip_text("Changed since the profile was last saved")
+                .css_classes(["accent"])
+                .build();
+            item.bind_property("changed", &changed_badge, "visible")
+                .sync_create()
+                .build();
+            row.add_suffix(&changed_badge);
+
+            let linked_from = item.linked_from();
+            if !linked_from.is_empty() {
+                row.add_suffix(
+                    &gtk::Label::builder()
+                        .label("dev")
+                        .tooltip_text(format!("Linked for development from {}", linked_from))
+                        .valign(gtk::Align::Center)
+                        .css_classes(["caption-heading", "accent"])
+                        .build(),
+                );
+            }
+
+            // Filled in once the mod's been audited in the background
+            let high_risk_badge = gtk::Image::builder()
+                .icon_name("dialog-error-symbolic")
+                .css_classes(["error"])
+                .build();
+            item.bind_property("high-risk-findings", &high_risk_badge, "visible")
+                .transform_to(|_, findings: String| Some(!findings.is_empty()))
+                .sync_create()
+                .build();
+            item.bind_property("high-risk-findings", &high_risk_badge, "tooltip-text")
+                .transform_to(|_, findings: String| {
+                    Some(format!(
+                        "Its scripts make calls that can harm your computer:\n{}",
+                        findings
+                    ))
+                })
+                .sync_create()
+                .build();
+            row.add_suffix(&high_risk_badge);
+
+            // Mods that can run any code have to be approved once per profile before being enabled
+            item.connect_enabled_notify(clone!(
+                #[weak]
+                cfg,
+                #[weak]
+                row,
+                #[weak]
+                mod_list_models,
+                move |item| {
+                    if !item.enabled() || !item.unrestricted() {
+                        return;
+                    }
+                    let Some(profile) = mod_list_models
+                        .as_ref()
+                        .borrow()
+                        .iter()
+                        .find(|(_, mod_objs)| mod_objs.contains(item))
+                        .map(|(profile, _)| profile.to_owned())
+                    else {
+                        return;
+                    };
+                    let id = item.id();
+                    if cfg
+                        .profiles()
+                        .get(&profile)
+                        .is_some_and(|it| it.approved_unrestricted.contains(&id))
+                    {
+                        return;
+                    }
+
+                    item.set_enabled(false);
+                    present_unrestricted_dialog(&row, item, &cfg, profile, id);
+                }
+            ));
+
             // Show Apply button if not visible already
             item.connect_enabled_notify(clone!(
                 #[weak]
@@ -671,6 +881,73 @@ impl NoitadApplicationWindow {
                 }
             ));
 
+            // Drag and drop to change the load order
+            row.add_prefix(&gtk::Image::from_icon_name("list-drag-handle-symbolic"));
+
+            let drag_source = gtk::DragSource::builder()
+                .actions(gdk::DragAction::MOVE)
+                .content(&gdk::ContentProvider::for_value(&item.to_value()))
+                .build();
+            drag_source.connect_drag_begin(clone!(
+                #[weak]
+                row,
+                move |source, _| {
+                    source.set_icon(Some(&gtk::WidgetPaintable::new(Some(&row))), 0, 0);
+                }
+            ));
+            row.add_controller(drag_source);
+
+            let drop_target = gtk::DropTarget::new(ModObject::static_type(), gdk::DragAction::MOVE);
+            drop_target.connect_drop(clone!(
+                #[weak]
+                item,
+                #[weak]
+                model,
+                #[weak]
+                mod_list_models,
+                #[weak]
+                is_profile_modified,
+                #[weak]
+                button_save_mod_list,
+                #[upgrade_or]
+                false,
+                move |_, value, _, _| {
+                    let Ok(dragged) = value.get::<ModObject>() else {
+                        return false;
+                    };
+                    let (Some(from), Some(to)) = (model.find(&dragged), model.find(&item)) else {
+                        return false;
+                    };
+                    if from == to {
+                        return false;
+                    }
+                    model.remove(from);
+                    model.insert(to, &dragged);
+
+                    // The cached models are what get serialized on Apply
+                    let mut mod_list_models = mod_list_models.as_ref().borrow_mut();
+                    let Some((profile, mod_objs)) = mod_list_models
+                        .iter_mut()
+                        .find(|(_, mod_objs)| mod_objs.contains(&dragged))
+                    else {
+                        return true;
+                    };
+                    let mod_obj = mod_objs.remove(from as usize);
+                    mod_objs.insert(to as usize, mod_obj);
+
+                    is_profile_modified
+                        .as_ref()
+                        .borrow_mut()
+                        .insert(profile.to_owned(), true);
+                    if button_save_mod_list.is_sensitive() {
+                        button_save_mod_list.set_visible(true);
+                    }
+
+                    true
+                }
+            ));
+            row.add_controller(drop_target);
+
             row.into()
         });
 
@@ -685,6 +962,7 @@ impl NoitadApplicationWindow {
                 let mod_list_models = imp.mod_list_models.clone();
                 let mod_list_models_ref = mod_list_models.as_ref().borrow();
                 let mut profiles = imp.config.profiles();
+                let installed = InstalledMods::new(&imp.config.noita_path());
                 is_profile_modified
                     .as_ref()
                     .borrow()
@@ -698,19 +976,31 @@ impl NoitadApplicationWindow {
                             }
                         },
                     )
-                    .map(|profile| {
-                        (
-                            profile,
-                            mod_objs_to_mods(mod_list_models_ref.get(profile).unwrap()),
-                        )
-                    })
-                    .for_each(|(profile, mods)| {
+                    .for_each(|profile| {
                         info!(%profile, "Serializing");
+                        // Loaded again so that the rest of the profile's mod_config is kept as is
                         _ = profiles
-                            .borrow_mut()
-                            .update_profile(profile, &mods)
+                            .get_profile(profile)
+                            .and_then(|mods| {
+                                let mods = mod_objs_to_mods(
+                                    mods,
+                                    mod_list_models_ref.get(profile).unwrap(),
+                                );
+                                profiles.borrow_mut().update_profile(profile, &mods)?;
+                                profiles.update_mod_hashes(profile, &mods, &installed)
+                            })
                             .inspect_err(|e| error!(%e));
                     });
+                imp.config.set_profiles(profiles);
+
+                // The default profile is the one the game is currently using
+                if let Some(active_profile) = imp.config.active_profile() {
+                    if is_profile_modified.as_ref().borrow().get(&active_profile) == Some(&true) {
+                        imp.obj().apply_profile_to_noita(active_profile);
+                    }
+                }
+                // Everything's saved now, which also lets the game's changes be synced again
+                is_profile_modified.as_ref().borrow_mut().clear();
 
                 btn.set_visible(false);
                 btn.set_sensitive(true);
@@ -718,74 +1008,467 @@ impl NoitadApplicationWindow {
         ));
     }
 
+    fn setup_mod_config_watcher(&self) {
+        let imp = self.imp();
+        let cfg = imp.config.clone();
+
+        imp.banner_mod_config_drift.connect_button_clicked(clone!(
+            #[weak(rename_to = window)]
+            self,
+            move |_| {
+                if let Some(active_profile) = window.imp().config.active_profile() {
+                    window.apply_profile_to_noita(active_profile);
+                }
+            }
+        ));
+        cfg.connect_active_profile_notify(clone!(
+            #[weak(rename_to = window)]
+            self,
+            move |_| window.update_drift_banner()
+        ));
+
+        let Some(save_dir) = cfg.noita_path().save_dir() else {
+            return;
+        };
+
+        let (sender, receiver) = async_channel::unbounded();
+        match ModConfigWatcher::new(&save_dir, move || _ = sender.send_blocking(())) {
+            Ok(watcher) => _ = imp.mod_config_watcher.replace(Some(watcher)),
+            Err(err) => {
+                error!(%err, "Couldn't watch Noita's mod_config.xml");
+                return;
+            }
+        }
+
+        glib::spawn_future_local(clone!(
+            #[weak(rename_to = window)]
+            self,
+            async move {
+                while receiver.recv().await.is_ok() {
+                    if cfg.active_profile_sync() {
+                        window.sync_active_profile(&save_dir);
+                    }
+                    window.update_drift_banner();
+                }
+            }
+        ));
+    }
+
+    fn sync_active_profile(&self, save_dir: &Path) {
+        let imp = self.imp();
+        let Some(active_profile) = imp.config.active_profile() else {
+            return;
+        };
+        // Unsaved changes in the window take precedence over the game's
+        if imp
+            .is_profile_modified
+            .as_ref()
+            .borrow()
+            .get(&active_profile)
+            == Some(&true)
+        {
+            return;
+        }
+
+        match imp
+            .config
+            .profiles()
+            .sync_profile_with_noita(&active_profile, save_dir)
+        {
+            Ok(true) => {
+                info!(active_profile, "Synced profile with the game");
+                imp.mod_list_models
+                    .as_ref()
+                    .borrow_mut()
+                    .remove(&active_profile);
+
+                self.reload_mod_list(&active_profile);
+            }
+            Ok(false) => {}
+            Err(err) => error!(%err, "Couldn't sync profile with the game"),
+        }
+    }
+
+    /// Reselects the profile if it's the one being shown, so that its mod list is loaded again.
+    fn reload_mod_list(&self, profile: &str) {
+        let profiles_list = self.imp().profiles_list.get();
+        if self.selected_profile().as_deref() == Some(profile) {
+            let row = profiles_list.selected_row();
+            profiles_list.unselect_all();
+            profiles_list.select_row(row.as_ref());
+        }
+    }
+
+    /// Installs mods from archives dropped onto the window.
+    fn setup_archive_drop(&self) {
+        let drop_target = gtk::DropTarget::new(gdk::FileList::static_type(), gdk::DragAction::COPY);
+        drop_target.connect_drop(clone!(
+            #[weak(rename_to = obj)]
+            self,
+            #[upgrade_or]
+            false,
+            move |_, value, _, _| {
+                let Ok(files) = value.get::<gdk::FileList>() else {
+                    return false;
+                };
+                let archives = files
+                    .files()
+                    .into_iter()
+                    .filter_map(|it| it.path())
+                    .collect_vec();
+                if archives.is_empty() {
+                    return false;
+                }
+                obj.install_archives(archives);
+
+                true
+            }
+        ));
+        self.add_controller(drop_target);
+    }
+
+    fn install_archives(&self, archives: Vec<PathBuf>) {
+        let imp = self.imp();
+        let Some(local_mods) = imp.config.noita_path().local_mods() else {
+            error!("Couldn't find the game's mods directory");
+            return;
+        };
+
+        let mut installed = vec![];
+        let mut lines = vec![];
+        for archive in archives {
+            let file_name = archive
+                .file_name()
+                .map(|it| it.to_string_lossy().into_owned())
+                .unwrap_or_default();
+            match install_archive(&archive, &local_mods, false) {
+                Ok(info) => {
+                    lines.push(format!("Installed '{}' from {}", info.title, file_name));
+                    installed.push(info);
+                }
+                Err(err) => {
+                    error!(%err, ?archive, "Couldn't install mod");
+                    lines.push(format!("Couldn't install {}: {}", file_name, err));
+                }
+            }
+        }
+        // Rescanned the next time it's needed, to pick up the new mods
+        imp.mod_catalog.as_ref().borrow_mut().take();
+
+        let dialog = adw::AlertDialog::builder()
+            .heading(match installed.is_empty() {
+                true => "Couldn't Install Mods",
+                false => "Mods Installed",
+            })
+            .body(lines.join("\n"))
+            .close_response("close")
+            .build();
+        dialog.add_response("close", "Close");
+        if !installed.is_empty() && imp.config.active_profile().is_some() {
+            dialog.add_response("add", "Add to Default Profile");
+            dialog.set_response_appearance("add", adw::ResponseAppearance::Suggested);
+        }
+        dialog.choose(
+            self,
+            None::<&gio::Cancellable>,
+            clone!(
+                #[weak(rename_to = obj)]
+                self,
+                move |resp| {
+                    if resp.as_str() == "add" {
+                        obj.add_to_default_profile(installed);
+                    }
+                }
+            ),
+        );
+    }
+
+    /// Adds newly installed mods to the default profile as unsaved changes, mods requesting
+    /// unrestricted API access are left disabled until approved.
+    fn add_to_default_profile(&self, mods: Vec<ModInfo>) {
+        let imp = self.imp();
+        let cfg = &imp.config;
+        let Some(profile) = cfg.active_profile() else {
+            return;
+        };
+        let profiles = cfg.profiles();
+        let approved = profiles
+            .get(&profile)
+            .map(|it| it.approved_unrestricted.clone())
+            .unwrap_or_default();
+
+        let mut mod_list_models = imp.mod_list_models.as_ref().borrow_mut();
+        if !mod_list_models.contains_key(&profile) {
+            let mut mod_catalog = imp.mod_catalog.as_ref().borrow_mut();
+            let catalog = mod_catalog.get_or_insert_with(|| ModCatalog::scan(&cfg.noita_path()));
+            Self::get_profile_mod_objs(
+                &profiles,
+                &profile,
+                catalog,
+                &InstalledMods::new(&cfg.noita_path()),
+                &cfg.linked_mods(),
+                mod_list_models,
+            );
+            mod_list_models = imp.mod_list_models.as_ref().borrow_mut();
+        }
+        let Some(mod_objs) = mod_list_models.get_mut(&profile) else {
+            return;
+        };
+        let mut added = vec![];
+        for info in mods {
+            let enabled = !info.request_no_api_restrictions || approved.contains(&info.id);
+            if let Some(mod_obj) = mod_objs.iter().find(|it| it.id() == info.id) {
+                mod_obj.set_enabled(mod_obj.enabled() || enabled);
+                continue;
+            }
+            let mut mod_ = Mod::new(&info.id.name, 0);
+            mod_.enabled = enabled;
+            let mod_obj = ModObject::new(mod_, Some(&info));
+            mod_objs.push(mod_obj.clone());
+            added.push((mod_obj, Some(info)));
+        }
+        drop(mod_list_models);
+        let (added, infos) = added.into_iter().unzip();
+        Self::scan_mods_in_background(added, infos, || Ok(vec![]));
+
+        imp.is_profile_modified
+            .as_ref()
+            .borrow_mut()
+            .insert(profile.clone(), true);
+        let button_save_mod_list = imp.button_save_mod_list.get();
+        if button_save_mod_list.is_sensitive() {
+            button_save_mod_list.set_visible(true);
+        }
+        self.reload_mod_list(&profile);
+    }
+
+    /// Reveals the banner if the game's mod list doesn't match the active profile anymore,
+    /// as long as that profile is the one being shown.
+    fn update_drift_banner(&self) {
+        let imp = self.imp();
+        let cfg = &imp.config;
+
+        let mut is_drifted = false;
+        if let (Some(active_profile), Some(save_dir)) =
+            (cfg.active_profile(), cfg.noita_path().save_dir())
+        {
+            if self.selected_profile().as_ref() == Some(&active_profile) {
+                is_drifted = cfg
+                    .profiles()
+                    .diff_with_noita(&active_profile, save_dir)
+                    .inspect_err(|err| error!(%err))
+                    .is_ok_and(|diff| !diff.is_empty());
+            }
+        }
+
+        imp.banner_mod_config_drift.set_revealed(is_drifted);
+    }
+
+    fn selected_profile(&self) -> Option<String> {
+        self.imp()
+            .profiles_list
+            .selected_row()
+            .and_then(|row| row.downcast::<adw::ActionRow>().ok())
+            .map(|row| row.title().into())
+    }
+
+    /// Writes the profile into the game's `mod_config.xml`, or defers it until the game exits
+    /// if it's running, since the game would overwrite it on exit otherwise.
+    fn apply_profile_to_noita(&self, profile: String) {
+        let cfg = self.imp().config.clone();
+        self.run_when_noita_closed(
+            "The game overwrites its mod list when it exits, so changes to the default profile can only be applied after it's closed.",
+            move || {
+                let Some(save_dir) = cfg.noita_path().save_dir() else {
+                    error!("Couldn't find Noita's save directory");
+                    return;
+                };
+                _ = cfg
+                    .profiles()
+                    .overwrite_with_profile(&profile, save_dir)
+                    .inspect_err(|e| error!(%e));
+            },
+        );
+    }
+
+    /// Makes the profile the default one, switching the game's mod list and save over to it.
+    fn switch_profile(&self, profile: String) {
+        let cfg = self.imp().config.clone();
+        self.run_when_noita_closed(
+            "The game can only be switched over to another profile after it's closed.",
+            move || {
+                let mut simple = cfg.to_simple_config();
+                let res = simple
+                    .profiles
+                    .get_profile(&profile)
+                    .and_then(|mods| simple.switch_profile(&profile, &mods));
+                match res {
+                    Ok(_) => {
+                        cfg.set_active_profile(Some(profile.as_str()));
+                        // Stored right away, as which save is in use depends on it
+                        _ = simple.store().inspect_err(|e| error!(%e));
+                    }
+                    Err(err) => error!(%err, "Couldn't switch profile"),
+                }
+            },
+        );
+    }
+
+    /// Runs the task right away, or asks whether to run it once Noita exits if the game is running.
+    fn run_when_noita_closed(&self, body: &str, task: impl FnOnce() + 'static) {
+        let Some(process) = NoitaProcess::find() else {
+            task();
+            return;
+        };
+
+        let dialog = adw::AlertDialog::builder()
+            .heading("Noita Is Running")
+            .body(body)
+            .close_response("cancel")
+            .build();
+        dialog.add_responses(&[("cancel", "Cancel"), ("defer", "Apply When Closed")]);
+        dialog.set_response_appearance("defer", adw::ResponseAppearance::Suggested);
+        dialog.choose(self, None::<&gio::Cancellable>, move |resp| {
+            if resp.as_str() != "defer" {
+                return;
+            }
+            let mut task = Some(task);
+            glib::timeout_add_seconds_local(2, move || {
+                if process.is_running() {
+                    return glib::ControlFlow::Continue;
+                }
+                info!(pid = process.pid, "Noita exited, running deferred task");
+                if let Some(task) = task.take() {
+                    task();
+                }
+                glib::ControlFlow::Break
+            });
+        });
+    }
+
+    /// Gives the profile a save of its own, or has it go back to the shared one.
+    fn set_isolated_save(&self, profile: &str, isolated: bool) -> Result<()> {
+        let cfg = &self.imp().config;
+        let mut simple = cfg.to_simple_config();
+        simple.set_isolated_save(profile, isolated)?;
+        simple.store()?;
+        cfg.set_profiles(ModProfiles(simple.profiles));
+
+        Ok(())
+    }
+
+    fn remove_profile(&self, profile: &str) -> Result<()> {
+        let cfg = &self.imp().config;
+        let mut simple = cfg.to_simple_config();
+        simple.remove_profile(profile)?;
+        simple.store()?;
+        cfg.set_profiles(ModProfiles(simple.profiles));
+
+        Ok(())
+    }
+
     fn get_profile_mod_objs(
         profiles: &ModProfiles,
         active: impl AsRef<str>,
+        catalog: &ModCatalog,
+        installed: &InstalledMods,
+        linked: &LinkedMods,
         mut mods_store: RefMut<HashMap<String, Vec<ModObject>>>,
     ) -> Vec<ModObject> {
         let mods = profiles.get_profile(active.as_ref()).unwrap();
+        let infos = mods
+            .mods
+            .iter()
+            .map(|it| catalog.get_mod(it).cloned())
+            .collect_vec();
         let mod_objs = mods
             .mods
             .into_iter()
-            .map(|it| ModObject::new(it))
+            .zip(infos.iter())
+            .map(|(it, info)| {
+                let missing = installed.is_installed(&it) == Some(false);
+                let linked_from = linked
+                    .get(&it.name)
+                    .filter(|_| it.is_local())
+                    .map(|it| it.source.to_string_lossy().into_owned());
+                let mod_obj = ModObject::new(it, info.as_ref());
+                mod_obj.set_missing(missing);
+                if let Some(source) = linked_from {
+                    mod_obj.set_linked_from(source);
+                }
+
+                mod_obj
+            })
             .collect_vec();
         mods_store.insert(active.as_ref().to_owned(), mod_objs.clone());
 
+        let (profiles, profile, installed) = (
+            profiles.0.clone(),
+            active.as_ref().to_owned(),
+            installed.clone(),
+        );
+        Self::scan_mods_in_background(mod_objs.clone(), infos, move || {
+            profiles.changed_mods(profile, &installed)
+        });
+
         mod_objs
     }
 
-    pub fn present_profile_new_dialog(&self, cb: impl FnOnce() + 'static) {
-        fn dialog_profile() -> (adw::AlertDialog, adw::EntryRow) {
-            let dialog = adw::AlertDialog::builder()
-                .close_response("cancel")
-                .heading("New Profile")
-                .build();
-
-            dialog.add_responses(&[("cancel", "Cancel"), ("create", "Create")]);
-            dialog.set_response_appearance("create", adw::ResponseAppearance::Suggested);
-            dialog.set_response_enabled("create", false);
-
-            let box_ = gtk::Box::builder()
-                .margin_top(12)
-                .spacing(24)
-                .orientation(gtk::Orientation::Vertical)
-                .build();
-            let list_box = gtk::ListBox::builder()
-                .selection_mode(gtk::SelectionMode::None)
-                .build();
-            list_box.add_css_class("boxed-list");
-            let entry_row = adw::EntryRow::builder().title("Profile Name").build();
+    /// Hashing and auditing mods goes through all of their files, so their badges are filled in
+    /// from a background thread once that's done.
+    fn scan_mods_in_background(
+        mod_objs: Vec<ModObject>,
+        infos: Vec<Option<ModInfo>>,
+        changed_mods: impl FnOnce() -> Result<Vec<ModId>> + Send + 'static,
+    ) {
+        glib::spawn_future_local(async move {
+            let res = gio::spawn_blocking(move || {
+                let changed = changed_mods()
+                    .inspect_err(|err| error!(%err, "Couldn't check for changed mods"))
+                    .unwrap_or_default();
+                let audits = infos
+                    .iter()
+                    .map(|info| {
+                        ModAudit::scan(info.as_ref()?)
+                            .inspect_err(|err| error!(%err, "Couldn't audit mod"))
+                            .ok()
+                    })
+                    .collect_vec();
+                (changed, audits)
+            })
+            .await;
+            let Ok((changed, audits)) = res else {
+                error!("Scanning mods panicked");
+                return;
+            };
 
-            entry_row.connect_text_notify(clone!(
-                #[weak]
-                dialog,
-                move |entry| {
-                    let text = entry.text();
-                    if !text.is_empty() {
-                        dialog.set_response_enabled("create", true);
-                    } else {
-                        dialog.set_response_enabled("create", false);
-                    }
+            for (mod_obj, audit) in mod_objs.iter().zip(audits) {
+                mod_obj.set_changed(changed.contains(&mod_obj.id()));
+                if let Some(audit) = audit {
+                    mod_obj.set_audit(&audit);
                 }
-            ));
-
-            list_box.append(&entry_row);
-            box_.append(&list_box);
-            dialog.set_extra_child(Some(&box_));
-
-            (dialog, entry_row)
-        }
+            }
+        });
+    }
 
-        let (dialog, entry_row) = dialog_profile();
+    pub fn present_profile_new_dialog(&self, cb: impl FnOnce() + 'static) {
+        let (dialog, entry_row) = dialog_profile_name("New Profile", ("create", "Create"), "");
         let cfg = self.imp().config.clone();
         dialog.choose(self, None::<&gio::Cancellable>, move |resp| {
             let text = entry_row.text();
             if resp.as_str() == "create" && !text.is_empty() {
                 let save_dir = cfg.noita_path().save_dir().unwrap();
-                let mut profiles = (&cfg).profiles();
+                let mut profiles = cfg.profiles();
                 let profile = text.to_string();
                 _ = profiles
                     .add_profile(text, save_dir)
+                    .and_then(|mods| {
+                        profiles.record_mod_hashes(
+                            &profile,
+                            &mods,
+                            &InstalledMods::new(&cfg.noita_path()),
+                        )
+                    })
                     .inspect_err(|e| error!(%e));
                 cfg.set_profiles(profiles);
                 if cfg.active_profile().is_none() {
@@ -797,13 +1480,467 @@ impl NoitadApplicationWindow {
 
         // todo: Toast for failure/success
     }
-}
 
-fn mod_objs_to_mods(mod_objs: &[ModObject]) -> Mods {
-    Mods {
-        mods: mod_objs
+    pub fn present_profile_rename_dialog(&self, profile: String) {
+        let (dialog, entry_row) =
+            dialog_profile_name("Rename Profile", ("rename", "Rename"), &profile);
+        let cfg = self.imp().config.clone();
+        let mod_list_models = self.imp().mod_list_models.clone();
+        let is_profile_modified = self.imp().is_profile_modified.clone();
+        dialog.choose(self, None::<&gio::Cancellable>, move |resp| {
+            let new_name = entry_row.text().to_string();
+            if resp.as_str() != "rename" || new_name.is_empty() || new_name == profile {
+                return;
+            }
+
+            let mut profiles = cfg.profiles();
+            if let Err(err) = profiles.rename_profile(&profile, &new_name) {
+                error!(%err);
+                return;
+            }
+
+            // Cached models and unsaved changes are keyed by profile name
+            {
+                let mut mod_list_models = mod_list_models.as_ref().borrow_mut();
+                if let Some(mod_objs) = mod_list_models.remove(&profile) {
+                    mod_list_models.insert(new_name.clone(), mod_objs);
+                }
+                let mut is_profile_modified = is_profile_modified.as_ref().borrow_mut();
+                if let Some(is_modified) = is_profile_modified.remove(&profile) {
+                    is_profile_modified.insert(new_name.clone(), is_modified);
+                }
+            }
+
+            let is_default = cfg.active_profile().as_deref() == Some(profile.as_str());
+            cfg.set_profiles(profiles);
+            if is_default {
+                cfg.set_active_profile(Some(new_name));
+            }
+        });
+    }
+
+    pub fn present_profile_duplicate_dialog(&self, profile: String) {
+        let (dialog, entry_row) = dialog_profile_name(
+            "Duplicate Profile",
+            ("duplicate", "Duplicate"),
+            &format!("{profile} (Copy)"),
+        );
+        let cfg = self.imp().config.clone();
+        dialog.choose(self, None::<&gio::Cancellable>, move |resp| {
+            let new_name = entry_row.text();
+            if resp.as_str() == "duplicate" && !new_name.is_empty() {
+                let mut profiles = cfg.profiles();
+                _ = profiles
+                    .duplicate_profile(&profile, new_name)
+                    .inspect_err(|e| error!(%e));
+                cfg.set_profiles(profiles);
+            }
+        });
+    }
+
+    /// Shows the errors in the game's log, grouped by the mods of the default profile.
+    fn present_diagnostics_dialog(&self) {
+        let imp = self.imp();
+        let cfg = &imp.config;
+        let mods = cfg
+            .active_profile()
+            .and_then(|profile| {
+                cfg.profiles()
+                    .get_profile(&profile)
+                    .inspect_err(|err| error!(%err))
+                    .ok()
+            })
+            .unwrap_or_default();
+        let catalog = imp
+            .mod_catalog
+            .as_ref()
+            .borrow_mut()
+            .get_or_insert_with(|| ModCatalog::scan(&cfg.noita_path()))
+            .clone();
+
+        DiagnosticsDialog::new(cfg.noita_path().game_root(), mods, catalog).present(Some(self));
+    }
+
+    /// Starts bisecting the profile's mods after asking for confirmation, or resumes the bisect
+    /// already in progress.
+    pub fn present_bisect_dialog(&self, profile: String) {
+        let running = Bisect::load()
+            .inspect_err(|err| error!(%err, "Couldn't load the bisect in progress"))
+            .ok()
+            .flatten();
+        if let Some(bisect) = running {
+            self.show_bisect_dialog(bisect);
+            return;
+        }
+
+        let dialog = adw::AlertDialog::builder()
+            .close_response("cancel")
+            .heading("Find Broken Mod?")
+            .body(format!(
+                "The game's mod list will be narrowed down to fewer and fewer of the mods enabled in '{profile}', until the ones causing the problem are found. The profile is made the default one if it isn't already."
+            ))
+            .build();
+        dialog.add_responses(&[("cancel", "Cancel"), ("start", "Start")]);
+        dialog.set_response_appearance("start", adw::ResponseAppearance::Suggested);
+        dialog.choose(
+            self,
+            None::<&gio::Cancellable>,
+            clone!(
+                #[weak(rename_to = window)]
+                self,
+                move |resp| {
+                    if resp.as_str() != "start" {
+                        return;
+                    }
+                    match window.start_bisect(&profile) {
+                        Ok(bisect) => window.show_bisect_dialog(bisect),
+                        Err(err) => error!(%err, "Couldn't start bisecting"),
+                    }
+                }
+            ),
+        );
+    }
+
+    fn start_bisect(&self, profile: &str) -> Result<Bisect> {
+        let cfg = &self.imp().config;
+        let save_dir = cfg
+            .noita_path()
+            .save_dir()
+            .context("Couldn't find Noita's save directory")?;
+        let mut simple = cfg.to_simple_config();
+        let mods = simple.profiles.get_profile(profile)?;
+        if cfg.active_profile().as_deref() != Some(profile) {
+            // So that the profile's save is the one being played
+            simple.switch_profile(profile, &mods)?;
+            simple.store()?;
+            cfg.set_active_profile(Some(profile));
+        }
+
+        Bisect::start(profile, &mods, save_dir)
+    }
+
+    fn show_bisect_dialog(&self, bisect: Bisect) {
+        let imp = self.imp();
+        let cfg = &imp.config;
+        let Some(save_dir) = cfg.noita_path().save_dir() else {
+            error!("Couldn't find Noita's save directory");
+            return;
+        };
+        let mods = match cfg.profiles().get_profile(&bisect.profile) {
+            Ok(mods) => mods,
+            Err(err) => {
+                error!(%err);
+                return;
+            }
+        };
+        let catalog = imp
+            .mod_catalog
+            .as_ref()
+            .borrow_mut()
+            .get_or_insert_with(|| ModCatalog::scan(&cfg.noita_path()))
+            .clone();
+
+        BisectDialog::new(bisect, mods, save_dir, catalog).present(Some(self));
+    }
+
+    pub fn present_profile_compare_dialog(&self, profile: String) {
+        let cfg = self.imp().config.clone();
+        let others = cfg
+            .profiles()
+            .keys()
+            .filter(|it| **it != profile)
+            .sorted()
+            .cloned()
+            .collect_vec();
+        if others.is_empty() {
+            return;
+        }
+
+        let dialog = adw::AlertDialog::builder()
+            .close_response("cancel")
+            .heading("Compare Profile")
+            .body(format!("Pick a profile to compare '{profile}' with"))
+            .build();
+        dialog.add_responses(&[("cancel", "Cancel"), ("compare", "Compare")]);
+        dialog.set_response_appearance("compare", adw::ResponseAppearance::Suggested);
+
+        let list_box = gtk::ListBox::builder()
+            .selection_mode(gtk::SelectionMode::None)
+            .margin_top(12)
+            .build();
+        list_box.add_css_class("boxed-list");
+        let combo_row = adw::ComboRow::builder()
+            .title("Profile")
+            .model(&gtk::StringList::new(
+                &others.iter().map(|it| it.as_str()).collect_vec(),
+            ))
+            .build();
+        list_box.append(&combo_row);
+        dialog.set_extra_child(Some(&list_box));
+
+        dialog.choose(
+            self,
+            None::<&gio::Cancellable>,
+            clone!(
+                #[weak(rename_to = window)]
+                self,
+                move |resp| {
+                    if resp.as_str() != "compare" {
+                        return;
+                    }
+                    let Some(other) = others.get(combo_row.selected() as usize) else {
+                        return;
+                    };
+                    _ = window
+                        .present_profile_comparison(&profile, other)
+                        .inspect_err(|err| error!(%err));
+                }
+            ),
+        );
+    }
+
+    /// Shows both mod lists side by side, with the differences between them highlighted.
+    fn present_profile_comparison(&self, a: &str, b: &str) -> Result<()> {
+        let imp = self.imp();
+        let cfg = &imp.config;
+        let profiles = cfg.profiles();
+        let (mods_a, mods_b) = (profiles.get_profile(a)?, profiles.get_profile(b)?);
+        let diff = mods_a.diff(&mods_b);
+
+        let mut mod_catalog = imp.mod_catalog.as_ref().borrow_mut();
+        let catalog = mod_catalog.get_or_insert_with(|| ModCatalog::scan(&cfg.noita_path()));
+
+        let grid = gtk::Grid::builder()
+            .column_spacing(24)
+            .row_spacing(6)
+            .column_homogeneous(true)
+            .margin_top(12)
+            .margin_bottom(12)
+            .margin_start(12)
+            .margin_end(12)
+            .build();
+        for (column, name) in [a, b].into_iter().enumerate() {
+            let label = gtk::Label::builder().label(name).xalign(0.0).build();
+            label.add_css_class("heading");
+            grid.attach(&label, column as i32, 0, 1, 1);
+        }
+
+        let cell = |mod_: &Mod, css_class: Option<&str>| {
+            let label = gtk::Label::builder()
+                .label(catalog.title(mod_))
+                .tooltip_text(mod_.id().to_string())
+                .xalign(0.0)
+                .ellipsize(gtk::pango::EllipsizeMode::End)
+                .build();
+            if !mod_.enabled {
+                label.add_css_class("dim-label");
+            }
+            if let Some(css_class) = css_class {
+                label.add_css_class(css_class);
+            }
+            label
+        };
+        let status = |id: &ModId| {
+            if diff.added.contains(id) || diff.removed.contains(id) {
+                Some("accent")
+            } else if diff.enabled.contains(id) || diff.disabled.contains(id) {
+                Some("warning")
+            } else if diff.reordered.contains(id) {
+                Some("success")
+            } else {
+                None
+            }
+        };
+
+        // Rows follow the first profile, with mods only in the second one placed at the end
+        let mut row = 1;
+        for mod_ in mods_a.mods.iter() {
+            let id = mod_.id();
+            grid.attach(&cell(mod_, status(&id)), 0, row, 1, 1);
+            if let Some(other) = mods_b.position(&id).map(|i| &mods_b.mods[i]) {
+                grid.attach(&cell(other, status(&id)), 1, row, 1, 1);
+            }
+            row += 1;
+        }
+        for mod_ in mods_b
+            .mods
             .iter()
-            .map(|it| it.imp().inner.clone().into_inner().0)
-            .collect_vec(),
+            .filter(|it| diff.added.contains(&it.id()))
+        {
+            grid.attach(&cell(mod_, Some("accent")), 1, row, 1, 1);
+            row += 1;
+        }
+
+        let legend = gtk::Label::builder()
+            .label(if diff.is_empty() {
+                "Both profiles have the same mod list"
+            } else {
+                "Highlighted are mods that are only in one profile, enabled in only one of them, or were moved"
+            })
+            .wrap(true)
+            .margin_start(12)
+            .margin_end(12)
+            .margin_bottom(12)
+            .build();
+        legend.add_css_class("dim-label");
+
+        let content = gtk::Box::new(gtk::Orientation::Vertical, 0);
+        content.append(
+            &gtk::ScrolledWindow::builder()
+                .hscrollbar_policy(gtk::PolicyType::Never)
+                .propagate_natural_height(true)
+                .vexpand(true)
+                .child(&grid)
+                .build(),
+        );
+        content.append(&legend);
+
+        let toolbar_view = adw::ToolbarView::new();
+        toolbar_view.add_top_bar(&adw::HeaderBar::new());
+        toolbar_view.set_content(Some(&content));
+
+        adw::Dialog::builder()
+            .title("Compare Profiles")
+            .content_width(640)
+            .content_height(560)
+            .child(&toolbar_view)
+            .build()
+            .present(Some(self));
+
+        Ok(())
+    }
+}
+
+fn dialog_profile_name(
+    heading: &str,
+    (response_id, response_label): (&str, &str),
+    text: &str,
+) -> (adw::AlertDialog, adw::EntryRow) {
+    let dialog = adw::AlertDialog::builder()
+        .close_response("cancel")
+        .heading(heading)
+        .build();
+
+    dialog.add_responses(&[("cancel", "Cancel"), (response_id, response_label)]);
+    dialog.set_response_appearance(response_id, adw::ResponseAppearance::Suggested);
+    dialog.set_response_enabled(response_id, !text.is_empty());
+
+    let box_ = gtk::Box::builder()
+        .margin_top(12)
+        .spacing(24)
+        .orientation(gtk::Orientation::Vertical)
+        .build();
+    let list_box = gtk::ListBox::builder()
+        .selection_mode(gtk::SelectionMode::None)
+        .build();
+    list_box.add_css_class("boxed-list");
+    let entry_row = adw::EntryRow::builder()
+        .title("Profile Name")
+        .text(text)
+        .build();
+
+    let response_id = response_id.to_owned();
+    entry_row.connect_text_notify(clone!(
+        #[weak]
+        dialog,
+        move |entry| {
+            dialog.set_response_enabled(&response_id, !entry.text().is_empty());
+        }
+    ));
+
+    list_box.append(&entry_row);
+    box_.append(&list_box);
+    dialog.set_extra_child(Some(&box_));
+
+    (dialog, entry_row)
+}
+
+/// Asks whether to enable a mod requesting unrestricted API access, remembering the approval for the profile.
+fn present_unrestricted_dialog(
+    parent: &impl IsA<gtk::Widget>,
+    item: &ModObject,
+    cfg: &ConfigObject,
+    profile: String,
+    id: ModId,
+) {
+    let dialog = adw::AlertDialog::builder()
+        .close_response("cancel")
+        .heading("Enable Unrestricted Mod?")
+        .body(format!(
+            "'{}' requests unrestricted API access, which lets it run any code on your computer. Only enable it if you trust its author.",
+            item.title()
+        ))
+        .build();
+    dialog.add_responses(&[("cancel", "Cancel"), ("enable", "Enable")]);
+    dialog.set_response_appearance("enable", adw::ResponseAppearance::Destructive);
+    dialog.choose(
+        parent,
+        None::<&gio::Cancellable>,
+        clone!(
+            #[weak]
+            item,
+            #[weak]
+            cfg,
+            move |resp| {
+                if resp.as_str() != "enable" {
+                    return;
+                }
+                let mut profiles = cfg.profiles();
+                let Some(entry) = profiles.get_mut(&profile) else {
+                    return;
+                };
+                entry.approved_unrestricted.insert(id);
+                cfg.set_profiles(profiles);
+                item.set_enabled(true);
+            }
+        ),
+    );
+}
+
+/// Menu for the profile rows in the sidebar, the default profile can't be removed or set as default again.
+fn profile_row_menu(is_default: bool) -> gio::Menu {
+    let menu_model = gio::Menu::new();
+    if !is_default {
+        menu_model.append_item(&gio::MenuItem::new(
+            Some("Set as Default"),
+            Some("profile-row.set-default"),
+        ));
     }
+    menu_model.append_item(&gio::MenuItem::new(
+        Some("Rename Profile"),
+        Some("profile-row.rename"),
+    ));
+    menu_model.append_item(&gio::MenuItem::new(
+        Some("Duplicate Profile"),
+        Some("profile-row.duplicate"),
+    ));
+    menu_model.append_item(&gio::MenuItem::new(
+        Some("Separate Save"),
+        Some("profile-row.separate-save"),
+    ));
+    menu_model.append_item(&gio::MenuItem::new(
+        Some("Compare With…"),
+        Some("profile-row.compare"),
+    ));
+    menu_model.append_item(&gio::MenuItem::new(
+        Some("Find Broken Mod…"),
+        Some("profile-row.bisect"),
+    ));
+    if !is_default {
+        menu_model.append_item(&gio::MenuItem::new(
+            Some("Remove Profile"),
+            Some("profile-row.remove"),
+        ));
+    }
+
+    menu_model
+}
+
+fn mod_objs_to_mods(mut mods: Mods, mod_objs: &[ModObject]) -> Mods {
+    mods.mods = mod_objs
+        .iter()
+        .map(|it| it.imp().inner.clone().into_inner().0)
+        .collect_vec();
+
+    mods
 }